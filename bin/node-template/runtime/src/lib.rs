@@ -171,14 +171,43 @@ impl grandpa::Trait for Runtime {
 	type Event = Event;
 }
 
+parameter_types! {
+	pub const EnumSetSize: u32 = 64;
+	pub const MaxReclaimSteps: u32 = 16;
+	pub const IndexSpaceWarningThreshold: AccountIndex = 1_000_000;
+	pub const IndexDeposit: Balance = 500;
+	pub const DeadDepositPolicy: indices::DepositPolicy = indices::DepositPolicy::Slash;
+	pub const ReclaimPolicy: indices::ReclaimPolicy = indices::ReclaimPolicy::PreferHint;
+}
+
 impl indices::Trait for Runtime {
 	/// The type for recording indexing into the account enumeration. If this ever overflows, there
 	/// will be problems!
 	type AccountIndex = AccountIndex;
 	/// Use the standard means of resolving an index hint from an id.
 	type ResolveHint = indices::SimpleResolveHint<Self::AccountId, Self::AccountIndex>;
+	/// Any index of a dead account may be reclaimed.
+	type ReclaimGuard = ();
 	/// Determine whether an account is dead.
 	type IsDeadAccount = Balances;
+	/// Number of account IDs stored per enum set.
+	type EnumSetSize = EnumSetSize;
+	/// Number of slots a new account inspects for a reclaimable index.
+	type MaxReclaimSteps = MaxReclaimSteps;
+	/// New accounts take their hinted index first, if it is reclaimable.
+	type ReclaimPolicy = ReclaimPolicy;
+	/// Warn once only this many new indices remain.
+	type IndexSpaceWarningThreshold = IndexSpaceWarningThreshold;
+	/// Only root may forcibly assign an index.
+	type ForceOrigin = system::EnsureRoot<AccountId>;
+	/// Deposits for claimed indices are reserved in the native currency.
+	type Currency = Balances;
+	/// The deposit reserved for a claimed index.
+	type IndexDeposit = IndexDeposit;
+	/// Deposits left by dead accounts are slashed.
+	type DeadDepositPolicy = DeadDepositPolicy;
+	/// Slashed deposits are burnt.
+	type Slashed = ();
 	/// The ubiquitous event type.
 	type Event = Event;
 }
@@ -336,7 +365,7 @@ impl_runtime_apis! {
 
 	impl sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block> for Runtime {
 		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
-			Executive::validate_transaction(tx)
+			Indices::with_lookup_cache(|| Executive::validate_transaction(tx))
 		}
 	}
 
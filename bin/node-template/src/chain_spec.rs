@@ -125,6 +125,8 @@ fn testnet_genesis(initial_authorities: Vec<(AuraId, GrandpaId)>,
 		}),
 		indices: Some(IndicesConfig {
 			ids: endowed_accounts.clone(),
+			reserved: 0,
+			dead_ids: vec![],
 		}),
 		balances: Some(BalancesConfig {
 			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
@@ -241,8 +241,14 @@ pub fn testnet_genesis(
 		}),
 		pallet_indices: Some(IndicesConfig {
 			ids: endowed_accounts.iter().cloned()
-				.chain(initial_authorities.iter().map(|x| x.0.clone()))
+				.chain(
+					initial_authorities.iter()
+						.map(|x| x.0.clone())
+						.filter(|x| !endowed_accounts.contains(x))
+				)
 				.collect::<Vec<_>>(),
+			reserved: 0,
+			dead_ids: vec![],
 		}),
 		pallet_session: Some(SessionConfig {
 			keys: initial_authorities.iter().map(|x| {
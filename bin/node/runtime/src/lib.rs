@@ -80,8 +80,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to equal spec_version. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 202,
-	impl_version: 202,
+	spec_version: 227,
+	impl_version: 227,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -158,10 +158,31 @@ impl pallet_babe::Trait for Runtime {
 	type EpochChangeTrigger = pallet_babe::ExternalTrigger;
 }
 
+parameter_types! {
+	pub const EnumSetSize: u32 = 64;
+	pub const MaxReclaimSteps: u32 = 16;
+	pub const IndexSpaceWarningThreshold: AccountIndex = 1_000_000;
+	pub const IndexDeposit: Balance = 1 * DOLLARS;
+	pub const DeadDepositPolicy: pallet_indices::DepositPolicy =
+		pallet_indices::DepositPolicy::Slash;
+	pub const ReclaimPolicy: pallet_indices::ReclaimPolicy =
+		pallet_indices::ReclaimPolicy::PreferHint;
+}
+
 impl pallet_indices::Trait for Runtime {
 	type AccountIndex = AccountIndex;
 	type IsDeadAccount = Balances;
 	type ResolveHint = pallet_indices::SimpleResolveHint<Self::AccountId, Self::AccountIndex>;
+	type ReclaimGuard = ();
+	type EnumSetSize = EnumSetSize;
+	type MaxReclaimSteps = MaxReclaimSteps;
+	type ReclaimPolicy = ReclaimPolicy;
+	type IndexSpaceWarningThreshold = IndexSpaceWarningThreshold;
+	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+	type Currency = Balances;
+	type IndexDeposit = IndexDeposit;
+	type DeadDepositPolicy = DeadDepositPolicy;
+	type Slashed = Treasury;
 	type Event = Event;
 }
 
@@ -651,7 +672,7 @@ impl_runtime_apis! {
 
 	impl sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block> for Runtime {
 		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
-			Executive::validate_transaction(tx)
+			Indices::with_lookup_cache(|| Executive::validate_transaction(tx))
 		}
 	}
 
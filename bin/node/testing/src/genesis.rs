@@ -39,6 +39,8 @@ pub fn config(support_changes_trie: bool, code: Option<&[u8]>) -> GenesisConfig
 		}),
 		pallet_indices: Some(IndicesConfig {
 			ids: vec![alice(), bob(), charlie(), dave(), eve(), ferdie()],
+			reserved: 0,
+			dead_ids: vec![],
 		}),
 		pallet_balances: Some(BalancesConfig {
 			balances: vec![
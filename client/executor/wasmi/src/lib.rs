@@ -24,7 +24,7 @@ use sc_executor_common::{
 use std::{str, mem, cell::RefCell};
 use wasmi::{
 	Module, ModuleInstance, MemoryInstance, MemoryRef, TableRef, ImportsBuilder, ModuleRef,
-	memory_units::Pages, RuntimeValue::{I32, I64, self},
+	memory_units::{Pages, Bytes}, RuntimeValue::{I32, I64, self},
 };
 use codec::{Encode, Decode};
 use sp_core::{sandbox as sandbox_primitives, traits::Externalities};
@@ -127,6 +127,27 @@ impl<'a> FunctionContext for FunctionExecutor<'a> {
 		self.memory.get_into(address.into(), dest).map_err(|e| e.to_string())
 	}
 
+	fn with_memory_slice(
+		&self,
+		address: Pointer<u8>,
+		size: WordSize,
+		f: &mut dyn FnMut(&[u8]),
+	) -> WResult<()> {
+		self.memory.with_direct_access(|memory| {
+			let start = u32::from(address) as usize;
+			let data = start.checked_add(size as usize)
+				.and_then(|end| memory.get(start..end))
+				.ok_or_else(|| String::from("memory read is out of bounds"))?;
+			f(data);
+			Ok(())
+		})
+	}
+
+	fn memory_size(&self) -> WordSize {
+		let size: Bytes = self.memory.current_size().into();
+		size.0.min(WordSize::max_value() as usize) as WordSize
+	}
+
 	fn write_memory(&mut self, address: Pointer<u8>, data: &[u8]) -> WResult<()> {
 		self.memory.set(address.into(), data).map_err(|e| e.to_string())
 	}
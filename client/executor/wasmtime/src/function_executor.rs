@@ -196,6 +196,22 @@ impl<'a> FunctionContext for FunctionExecutor<'a> {
 		read_memory_into(self.memory, address, dest).map_err(|e| e.to_string())
 	}
 
+	fn with_memory_slice(
+		&self,
+		address: Pointer<u8>,
+		size: WordSize,
+		f: &mut dyn FnMut(&[u8]),
+	) -> WResult<()> {
+		let range = checked_range(address.into(), size as usize, self.memory.len())
+			.ok_or_else(|| String::from("memory read is out of bounds"))?;
+		f(&self.memory[range]);
+		Ok(())
+	}
+
+	fn memory_size(&self) -> WordSize {
+		self.memory.len().min(WordSize::max_value() as usize) as WordSize
+	}
+
 	fn write_memory(&mut self, address: Pointer<u8>, data: &[u8]) -> WResult<()> {
 		write_memory_from(self.memory, address, data).map_err(|e| e.to_string())
 	}
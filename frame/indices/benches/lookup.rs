@@ -0,0 +1,113 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks looking up one index of a full enum set under the v0 and the v1 storage layout.
+//!
+//! The pallet storage is private to the crate, so the module below declares both layouts the way
+//! the pallet does: v0 keeps a set as one `Vec` in `EnumSet`, v1 keeps every slot in `EnumSlot`.
+
+use criterion::{Criterion, criterion_group, criterion_main, black_box};
+use frame_system as system;
+use frame_support::{
+	decl_module, decl_storage, impl_outer_origin, weights::Weight, StorageMap, StorageDoubleMap,
+};
+use sp_core::{H256, crypto::AccountId32};
+use sp_runtime::{Perbill, traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+
+/// The size of an enum set in both layouts.
+const ENUM_SET_SIZE: u32 = 64;
+
+mod module {
+	use super::*;
+
+	pub trait Trait: system::Trait {}
+
+	decl_module! {
+		pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+	}
+
+	decl_storage! {
+		trait Store for Module<T: Trait> as Indices {
+			pub EnumSet: map u32 => Vec<AccountId32>;
+			pub EnumSlot: double_map u32, hasher(blake2_256) u32 => Option<AccountId32>;
+		}
+	}
+}
+
+impl_outer_origin!{
+	pub enum Origin for Runtime {}
+}
+
+frame_support::parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 4 * 1024 * 1024;
+	pub const MaximumBlockLength: u32 = 4 * 1024 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+}
+#[derive(Clone, Eq, PartialEq)]
+pub struct Runtime;
+impl system::Trait for Runtime {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Call = ();
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+}
+
+impl module::Trait for Runtime {}
+
+/// Externalities with the enum set `0` full under both layouts.
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t: sp_io::TestExternalities = system::GenesisConfig::default()
+		.build_storage::<Runtime>()
+		.unwrap()
+		.into();
+	t.execute_with(|| {
+		let accounts = (0..ENUM_SET_SIZE as u8)
+			.map(|i| AccountId32::from([i; 32]))
+			.collect::<Vec<_>>();
+		for (i, who) in accounts.iter().enumerate() {
+			module::EnumSlot::insert(0, i as u32, who);
+		}
+		module::EnumSet::insert(0, accounts);
+	});
+	t
+}
+
+fn lookup_benchmark(c: &mut Criterion) {
+	new_test_ext().execute_with(|| {
+		c.bench_function("lookup in a full v0 enum set", |b| {
+			b.iter(|| module::EnumSet::get(black_box(0)).get(black_box(37)).cloned())
+		});
+		c.bench_function("lookup in a full v1 enum set", |b| {
+			b.iter(|| module::EnumSlot::get(black_box(0), black_box(37)))
+		});
+	});
+}
+
+criterion_group!(benches, lookup_benchmark);
+criterion_main!(benches);
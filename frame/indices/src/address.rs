@@ -129,6 +129,58 @@ impl<AccountId, AccountIndex> Default for Address<AccountId, AccountIndex> where
 	}
 }
 
+/// The encoding version of [`VersionedAddress`] that wraps the current `Address` encoding.
+pub const ADDRESS_VERSION: u8 = 1;
+
+/// An [`Address`] whose encoding is prefixed with a version byte.
+///
+/// The version lets a decoder tell a future address format apart from a malformed address.
+/// Version [`ADDRESS_VERSION`] is followed by the unchanged `Address` encoding, other versions
+/// are rejected. Plain `Address` encodings are not affected.
+#[derive(PartialEq, Eq, Clone, sp_runtime::RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Hash))]
+pub struct VersionedAddress<AccountId, AccountIndex>(pub Address<AccountId, AccountIndex>) where
+	AccountId: Member,
+	AccountIndex: Member;
+
+impl<AccountId, AccountIndex> From<Address<AccountId, AccountIndex>>
+	for VersionedAddress<AccountId, AccountIndex>
+where
+	AccountId: Member,
+	AccountIndex: Member,
+{
+	fn from(a: Address<AccountId, AccountIndex>) -> Self {
+		VersionedAddress(a)
+	}
+}
+
+impl<AccountId, AccountIndex> Decode for VersionedAddress<AccountId, AccountIndex> where
+	AccountId: Member + Decode,
+	AccountIndex: Member + Decode + PartialOrd<AccountIndex> + Ord + From<u32> + Copy,
+{
+	fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+		match input.read_byte()? {
+			ADDRESS_VERSION => Ok(VersionedAddress(Decode::decode(input)?)),
+			_ => Err("Unknown address version".into()),
+		}
+	}
+}
+
+impl<AccountId, AccountIndex> Encode for VersionedAddress<AccountId, AccountIndex> where
+	AccountId: Member + Encode,
+	AccountIndex: Member + Encode + PartialOrd<AccountIndex> + Ord + Copy + From<u32> + TryInto<u32>,
+{
+	fn encode_to<T: Output>(&self, dest: &mut T) {
+		dest.push_byte(ADDRESS_VERSION);
+		self.0.encode_to(dest);
+	}
+}
+
+impl<AccountId, AccountIndex> codec::EncodeLike for VersionedAddress<AccountId, AccountIndex> where
+	AccountId: Member + Encode,
+	AccountIndex: Member + Encode + PartialOrd<AccountIndex> + Ord + Copy + From<u32> + TryInto<u32>,
+{}
+
 #[cfg(test)]
 mod tests {
 	use codec::{Encode, Decode};
@@ -155,4 +207,29 @@ mod tests {
 		compare(Some(index(0x10000)), &[253, 0, 0, 1, 0][..]);
 		compare(Some(id([42, 69, 42, 69, 42, 69, 42, 69])), &[255, 42, 69, 42, 69, 42, 69, 42, 69][..]);
 	}
+
+	#[test]
+	fn versioned_address_should_wrap_the_current_encoding() {
+		type VersionedAddress = super::VersionedAddress<[u8; 8], u32>;
+
+		for a in vec![index(2), index(240), index(0x10000), id([42; 8])] {
+			let encoded = VersionedAddress::from(a.clone()).encode();
+			assert_eq!(encoded[0], super::ADDRESS_VERSION);
+			assert_eq!(encoded[1..], a.encode()[..]);
+			assert_eq!(VersionedAddress::decode(&mut &encoded[..]), Ok(super::VersionedAddress(a)));
+		}
+	}
+
+	#[test]
+	fn unknown_address_version_should_not_decode() {
+		type VersionedAddress = super::VersionedAddress<[u8; 8], u32>;
+
+		for version in &[0u8, 2, 255] {
+			assert_eq!(
+				VersionedAddress::decode(&mut &[*version, 2][..]).unwrap_err().what(),
+				"Unknown address version",
+			);
+		}
+		assert!(VersionedAddress::decode(&mut &[][..]).is_err());
+	}
 }
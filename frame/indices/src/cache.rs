@@ -0,0 +1,79 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An in-memory cache of `lookup_index` results for the duration of a scope.
+
+use sp_std::{prelude::*, collections::btree_map::BTreeMap};
+use codec::{Encode, Decode};
+
+/// The memoized results of `lookup_index`.
+///
+/// A static can not be generic over the `Trait`, so the indices and accounts are kept SCALE
+/// encoded.
+#[derive(Default)]
+pub struct IndicesCache {
+	lookups: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+/// Access to the memoized results of a cache.
+///
+/// Without `std`, `environmental` only supports trait objects.
+pub trait LookupCache {
+	/// The encoded results, keyed by the encoded index.
+	fn lookups(&mut self) -> &mut BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+}
+
+impl LookupCache for IndicesCache {
+	fn lookups(&mut self) -> &mut BTreeMap<Vec<u8>, Option<Vec<u8>>> {
+		&mut self.lookups
+	}
+}
+
+environmental::environmental!(lookup_cache: trait LookupCache);
+
+/// Run `f` with a lookup cache. Nested calls use the cache of the outermost call.
+pub fn with_lookup_cache<R>(f: impl FnOnce() -> R) -> R {
+	if lookup_cache::with(|_| ()).is_some() {
+		return f()
+	}
+	lookup_cache::using(&mut IndicesCache::default(), f)
+}
+
+/// Return the cached result for `index` or, if there is none, cache the result of `lookup`.
+///
+/// Without a cache in scope, this is just `lookup()`.
+pub(crate) fn cached<I: Encode, A: Encode + Decode>(
+	index: &I,
+	lookup: impl FnOnce() -> Option<A>,
+) -> Option<A> {
+	let key = index.encode();
+	match lookup_cache::with(|cache| cache.lookups().get(&key).cloned()) {
+		None => lookup(),
+		Some(Some(who)) => who.and_then(|who| A::decode(&mut &who[..]).ok()),
+		Some(None) => {
+			let who = lookup();
+			lookup_cache::with(|cache| {
+				cache.lookups().insert(key, who.as_ref().map(Encode::encode))
+			});
+			who
+		}
+	}
+}
+
+/// Drop the cached result for `index`, if there is a cache in scope.
+pub(crate) fn forget<I: Encode>(index: &I) {
+	lookup_cache::with(|cache| cache.lookups().remove(&index.encode()));
+}
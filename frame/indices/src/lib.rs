@@ -19,24 +19,41 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use sp_std::{prelude::*, marker::PhantomData, convert::TryInto};
-use codec::{Encode, Codec};
-use frame_support::{Parameter, decl_module, decl_event, decl_storage};
-use sp_runtime::traits::{One, SimpleArithmetic, StaticLookup, Member, LookupError};
-use frame_system::{IsDeadAccount, OnNewAccount};
+use sp_std::{
+	prelude::*, marker::PhantomData, convert::TryInto, iter, collections::btree_map::BTreeMap,
+};
+use codec::{Encode, Decode, Codec};
+use frame_support::{
+	Parameter, decl_module, decl_error, decl_event, decl_storage, ensure, debug::native,
+	traits::{Get, Currency, ReservableCurrency, OnUnbalanced},
+	weights::{SimpleDispatchInfo, Weight, WeighData, ClassifyDispatch, DispatchClass, PaysFee},
+};
+use sp_runtime::{
+	RuntimeDebug,
+	traits::{
+		Zero, One, Bounded, CheckedAdd, CheckedMul, Saturating, SimpleArithmetic, StaticLookup,
+		Member, LookupError, EnsureOrigin,
+	},
+	DispatchError,
+};
+use frame_system::{IsDeadAccount, OnNewAccount, ensure_signed, ensure_root};
 
 use self::address::Address as RawAddress;
 
 mod mock;
 
+mod cache;
 pub mod address;
+pub mod migration;
 mod tests;
 
-/// Number of account IDs stored per enum set.
-const ENUM_SET_SIZE: u32 = 64;
-
 pub type Address<T> = RawAddress<<T as frame_system::Trait>::AccountId, <T as Trait>::AccountIndex>;
 
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
 /// Turn an Id into an Index, or None for the purpose of getting
 /// a hint at a possibly desired index.
 pub trait ResolveHint<AccountId, AccountIndex> {
@@ -46,15 +63,125 @@ pub trait ResolveHint<AccountId, AccountIndex> {
 }
 
 /// Simple encode-based resolve hint implementation.
+///
+/// Reads the first two bytes of the encoded account id. Returns `None` if the encoding is too
+/// short.
 pub struct SimpleResolveHint<AccountId, AccountIndex>(PhantomData<(AccountId, AccountIndex)>);
 impl<AccountId: Encode, AccountIndex: From<u32>>
 	ResolveHint<AccountId, AccountIndex> for SimpleResolveHint<AccountId, AccountIndex>
 {
 	fn resolve_hint(who: &AccountId) -> Option<AccountIndex> {
-		Some(AccountIndex::from(who.using_encoded(|e| e[0] as u32 + e[1] as u32 * 256)))
+		who.using_encoded(|e| {
+			e.get(..2).map(|b| AccountIndex::from(b[0] as u32 + b[1] as u32 * 256))
+		})
 	}
 }
 
+/// Encode-based resolve hint implementation that reads the two bytes starting at `Offset`.
+///
+/// Useful when the leading bytes of the encoded account id carry little entropy. Returns `None`
+/// if the encoding is too short.
+pub struct OffsetResolveHint<AccountId, AccountIndex, Offset>(
+	PhantomData<(AccountId, AccountIndex, Offset)>
+);
+impl<AccountId: Encode, AccountIndex: From<u32>, Offset: Get<usize>>
+	ResolveHint<AccountId, AccountIndex> for OffsetResolveHint<AccountId, AccountIndex, Offset>
+{
+	fn resolve_hint(who: &AccountId) -> Option<AccountIndex> {
+		let offset = Offset::get();
+		who.using_encoded(|e| {
+			let end = offset.checked_add(2)?;
+			e.get(offset..end).map(|b| AccountIndex::from(b[0] as u32 + b[1] as u32 * 256))
+		})
+	}
+}
+
+/// Resolve hint implementation that maps the hint of `Inner` into `[Min, Max)`.
+///
+/// Useful to keep hinted indices out of reserved ranges. Returns `None` if `Inner` does, or if the
+/// range is empty.
+pub struct RangedResolveHint<Inner, Min, Max>(PhantomData<(Inner, Min, Max)>);
+impl<AccountId, AccountIndex, Inner, Min, Max>
+	ResolveHint<AccountId, AccountIndex> for RangedResolveHint<Inner, Min, Max>
+where
+	AccountIndex: SimpleArithmetic + Copy,
+	Inner: ResolveHint<AccountId, AccountIndex>,
+	Min: Get<AccountIndex>,
+	Max: Get<AccountIndex>,
+{
+	fn resolve_hint(who: &AccountId) -> Option<AccountIndex> {
+		let (min, max) = (Min::get(), Max::get());
+		if max <= min {
+			return None
+		}
+		Inner::resolve_hint(who).map(|hint| min + hint % (max - min))
+	}
+}
+
+/// Decides whether the index of a dead account may be reclaimed.
+///
+/// Lets other modules keep an index that still has on-chain significance, even though
+/// `IsDeadAccount` reports its holder as dead.
+pub trait ReclaimGuard<AccountId, AccountIndex> {
+	/// `true` if `index`, held by the dead account `who`, may be handed to another account.
+	fn can_reclaim(who: &AccountId, index: AccountIndex) -> bool;
+}
+
+/// Allows all reclaims.
+impl<AccountId, AccountIndex> ReclaimGuard<AccountId, AccountIndex> for () {
+	fn can_reclaim(_: &AccountId, _: AccountIndex) -> bool {
+		true
+	}
+}
+
+/// The reason why an index could not be looked up.
+#[derive(Eq, PartialEq, Clone, Copy, RuntimeDebug)]
+pub enum LookupIndexError {
+	/// The index belongs to an enum set that was not allocated yet.
+	OutOfRange,
+	/// The slot of the index is empty or was not allocated yet.
+	Unassigned,
+}
+
+/// What happens to the deposit of an index that is reclaimed from a dead account.
+#[derive(Eq, PartialEq, Clone, Copy, RuntimeDebug)]
+pub enum DepositPolicy {
+	/// The deposit is returned to the dead account.
+	Return,
+	/// The deposit is slashed and handed to `Trait::Slashed`.
+	Slash,
+}
+
+/// Which reclaimable index a new account takes first.
+#[derive(Eq, PartialEq, Clone, Copy, RuntimeDebug)]
+pub enum ReclaimPolicy {
+	/// The index given by `Trait::ResolveHint`, then the most recently freed index, then the next
+	/// reclaimable index after the one found by the previous search.
+	PreferHint,
+	/// The most recently freed index, then the hinted index, then the next reclaimable index
+	/// after the one found by the previous search.
+	PreferFreeList,
+	/// The lowest reclaimable index, to keep the index space dense, then the hinted index, then
+	/// the most recently freed index.
+	///
+	/// Every new account inspects up to `Trait::MaxReclaimSteps` slots from `LowestReclaimCursor`
+	/// on, even if the hinted index is reclaimable. The cursor is moved past the inspected slots
+	/// and back to every index added to `FreeList`, so freed indices are taken lowest first
+	/// however many live indices are below them. `FreeList` is searched with as many steps again
+	/// if none of the inspected slots is reclaimable. Indices of dead accounts below the cursor
+	/// are only taken through the hint, or once `clear_dead_indices` frees them.
+	LowestFirst,
+}
+
+/// An entry of `FreeList`, linking a freed index to its neighbours in the list.
+#[derive(Encode, Decode, Eq, PartialEq, Clone, Copy, RuntimeDebug)]
+pub struct FreeListEntry<AccountIndex> {
+	/// The listed index that was freed right before this one.
+	pub older: Option<AccountIndex>,
+	/// The listed index that was freed right after this one.
+	pub newer: Option<AccountIndex>,
+}
+
 /// The module's config trait.
 pub trait Trait: frame_system::Trait {
 	/// Type used for storing an account's index; implies the maximum number of accounts the system
@@ -67,13 +194,459 @@ pub trait Trait: frame_system::Trait {
 	/// How to turn an id into an index.
 	type ResolveHint: ResolveHint<Self::AccountId, Self::AccountIndex>;
 
+	/// Whether the index of a dead account may be reclaimed.
+	type ReclaimGuard: ReclaimGuard<Self::AccountId, Self::AccountIndex>;
+
+	/// Number of account IDs stored per enum set.
+	///
+	/// Must not be zero, indices are split into their enum set and slot by this size. It must not
+	/// change either once indices are assigned, as that would move every index to another slot.
+	/// The storage of the v0 layout, which was built with a size of 64, is migrated to this size
+	/// without changing any index.
+	type EnumSetSize: Get<u32>;
+
+	/// The maximum number of slots a new account inspects for a reclaimable index, including the
+	/// entries of `FreeList`, before it is given a new index. With `ReclaimPolicy::LowestFirst`,
+	/// it inspects as many entries of `FreeList` on top.
+	type MaxReclaimSteps: Get<u32>;
+
+	/// Which reclaimable index a new account takes first.
+	type ReclaimPolicy: Get<ReclaimPolicy>;
+
+	/// The number of remaining indices at or below which `IndexSpaceNearlyExhausted` is
+	/// deposited, once.
+	type IndexSpaceWarningThreshold: Get<Self::AccountIndex>;
+
+	/// The origin which may forcibly assign an index. Root can always do this.
+	type ForceOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The currency in which index deposits are reserved.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The deposit reserved from the holder of an index that was claimed or transferred.
+	type IndexDeposit: Get<BalanceOf<Self>>;
+
+	/// What happens to the deposit of an index that is reclaimed from a dead account.
+	type DeadDepositPolicy: Get<DepositPolicy>;
+
+	/// What to do with slashed deposits.
+	type Slashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 }
 
+decl_error! {
+	/// Error for the indices module.
+	pub enum Error for Module<T: Trait> {
+		/// The index is held by an account that is still alive, or `T::ReclaimGuard` keeps it.
+		InUse,
+		/// The index is beyond the next free slot of its enum set, or its enum set is beyond the
+		/// one being filled.
+		NotContiguous,
+		/// The index is not held by the sender.
+		NotOwner,
+		/// The source and destination accounts are identical.
+		NotTransfer,
+		/// The index is reserved.
+		Reserved,
+		/// The index is not held by any account.
+		NotAssigned,
+		/// The enum set of the index is not migrated to the current storage layout yet.
+		NotMigrated,
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The weight of `claim`.
+	pub const CLAIM_WEIGHT: Weight = 50_000;
+	/// The weight of `free`.
+	pub const FREE_WEIGHT: Weight = 30_000;
+	/// The weight of `transfer`.
+	pub const TRANSFER_WEIGHT: Weight = 40_000;
+	/// The weight of `force_set_index`.
+	pub const FORCE_SET_INDEX_WEIGHT: Weight = 70_000;
+	/// The weight of `force_reclaim`.
+	pub const FORCE_RECLAIM_WEIGHT: Weight = 35_000;
+	/// The weight of `clear_dead_indices`, before inspecting any slot.
+	pub const CLEAR_DEAD_INDICES_BASE_WEIGHT: Weight = 10_000;
+	/// The weight of each slot inspected by `clear_dead_indices`.
+	pub const CLEAR_DEAD_INDICES_SLOT_WEIGHT: Weight = 20_000;
+
+	/// The maximum number of indices returned by one call of `lookup_range`.
+	pub const MAX_LOOKUP_RANGE: u32 = 1024;
+}
+
+/// The weight of `clear_dead_indices`, which grows with the number of slots it may inspect.
+struct WeightForClearDeadIndices<T>(sp_std::marker::PhantomData<T>);
+
+impl<T: Trait> WeighData<(&u32,)> for WeightForClearDeadIndices<T> {
+	fn weigh_data(&self, (max,): (&u32,)) -> Weight {
+		Module::<T>::CLEAR_DEAD_INDICES_SLOT_WEIGHT
+			.saturating_mul(*max)
+			.saturating_add(Module::<T>::CLEAR_DEAD_INDICES_BASE_WEIGHT)
+	}
+}
+
+impl<T, A> ClassifyDispatch<A> for WeightForClearDeadIndices<T> {
+	fn classify_dispatch(&self, _: A) -> DispatchClass {
+		DispatchClass::Normal
+	}
+}
+
+impl<T> PaysFee for WeightForClearDeadIndices<T> {
+	fn pays_fee(&self) -> bool {
+		true
+	}
+}
+
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin, system = frame_system {
+		type Error = Error<T>;
+
 		fn deposit_event() = default;
+
+		fn on_initialize() {
+			debug_assert!(T::EnumSetSize::get() > 0, "The enum set size must not be zero.");
+			Self::ensure_storage_upgraded();
+		}
+
+		/// Number of account IDs stored per enum set.
+		const EnumSetSize: u32 = T::EnumSetSize::get();
+
+		/// The maximum number of slots a new account inspects for a reclaimable index.
+		const MaxReclaimSteps: u32 = T::MaxReclaimSteps::get();
+
+		/// The number of remaining indices at or below which a warning is deposited.
+		const IndexSpaceWarningThreshold: T::AccountIndex = T::IndexSpaceWarningThreshold::get();
+
+		/// The deposit reserved from the holder of a claimed or transferred index.
+		const IndexDeposit: BalanceOf<T> = T::IndexDeposit::get();
+
+		/// Assign a specific index to the sender.
+		///
+		/// The index must either be held by a dead account, have been freed, or be the next free
+		/// slot of its enum set; slots within an enum set are allocated in order. Enum sets beyond
+		/// `NextEnumSet` can not be claimed, as their indices can not be looked up. The index of a
+		/// dead account can only be claimed if `T::ReclaimGuard` allows it. Neither can enum sets
+		/// that a storage migration did not reach yet.
+		///
+		/// `T::IndexDeposit` is reserved from the sender. The deposit held for a dead account is
+		/// returned or slashed according to `T::DeadDepositPolicy`.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Emits `NewAccountIndex` if successful.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One storage read of `EnumSetLen` and up to one write.
+		/// - With the `migrate` feature, one storage read of `StorageVersion` and up to one of
+		///   `MigrationCursor`.
+		/// - Up to one storage read and one write of the slot.
+		/// - Up to one storage write of `NextEnumSet`.
+		/// - Up to one storage read and write of `LiveIndexCount`.
+		/// - Up to one storage read of `FreeList`, and up to three writes and one of
+		///   `LastFreeIndex` if the index was listed.
+		/// - One reserve and up to one unreserve or slash of `T::Currency`.
+		/// - One storage read and write of `Deposits`.
+		/// - Up to one storage read and write of `IndexSpaceWarned`.
+		/// - Up to two events.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(Module::<T>::CLAIM_WEIGHT)]
+		fn claim(origin, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+
+			let (set_index, item_index) = Self::split_index(index)
+				.ok_or(Error::<T>::NotContiguous)?;
+			ensure!(set_index <= Self::next_enum_set(), Error::<T>::NotContiguous);
+			ensure!(migration::is_migrated::<T>(set_index), Error::<T>::NotMigrated);
+			ensure!(!Self::is_reserved(index), Error::<T>::Reserved);
+			let set_len = Self::enum_set_len(set_index);
+
+			if item_index < set_len {
+				let old = Self::enum_slot(set_index, item_index);
+				ensure!(Self::is_reclaimable(index, old.as_ref()), Error::<T>::InUse);
+				let deposit = Self::reserve_deposit(&who)?;
+				match old {
+					Some(old) => {
+						Self::release_dead_deposit(&old, index);
+						Self::forget_index(&old, index);
+					}
+					None => Self::note_empty_slot_taken(index),
+				}
+				<Deposits<T>>::insert(index, deposit);
+			} else {
+				ensure!(item_index == set_len, Error::<T>::NotContiguous);
+				let deposit = Self::reserve_deposit(&who)?;
+				<Deposits<T>>::insert(index, deposit);
+				<EnumSetLen<T>>::insert(set_index, set_len + 1);
+				<LiveIndexCount<T>>::mutate(|c| *c += One::one());
+
+				// keep NextEnumSet up to date
+				if set_index == Self::next_enum_set() && set_len + 1 == T::EnumSetSize::get() {
+					<NextEnumSet<T>>::put(set_index.saturating_add(One::one()));
+				}
+			}
+
+			<EnumSlot<T>>::insert(set_index, item_index, &who);
+			Self::note_index(&who, index);
+
+			Self::deposit_event(RawEvent::NewAccountIndex(who, index));
+			Self::note_index_space(index);
+		}
+
+		/// Give up an index held by the sender.
+		///
+		/// The slot is left empty and becomes available for reclaim immediately, even though the
+		/// sender is still alive. The indices of the other accounts in its enum set are unchanged.
+		/// The index is added to `FreeList`, so it is handed to one of the next new accounts.
+		/// The deposit held for the index is returned. Indices in enum sets that a storage
+		/// migration did not reach yet can not be given up.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Emits `IndexFreed` if successful.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - With the `migrate` feature, one storage read of `StorageVersion` and up to one of
+		///   `MigrationCursor`.
+		/// - One storage read and write of the slot.
+		/// - One storage read and write of `LiveIndexCount`.
+		/// - One storage read and write of `LastFreeIndex` and up to two writes of `FreeList`.
+		/// - One storage read and up to one write of `LowestReclaimCursor`.
+		/// - One storage read and write of `Deposits` and up to one unreserve of `T::Currency`.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(Module::<T>::FREE_WEIGHT)]
+		fn free(origin, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+
+			let (set_index, item_index) = Self::split_index(index)
+				.ok_or(Error::<T>::NotOwner)?;
+			ensure!(migration::is_migrated::<T>(set_index), Error::<T>::NotMigrated);
+
+			let owner = Self::enum_slot(set_index, item_index);
+			ensure!(owner.as_ref() == Some(&who), Error::<T>::NotOwner);
+
+			<EnumSlot<T>>::remove(set_index, item_index);
+			<LiveIndexCount<T>>::mutate(|c| *c -= One::one());
+			Self::push_free_index(index);
+			Self::forget_index(&who, index);
+			Self::return_deposit(&who, index);
+
+			Self::deposit_event(RawEvent::IndexFreed(who, index));
+		}
+
+		/// Hand an index held by the sender over to `new_owner`.
+		///
+		/// `new_owner` may already hold other indices. `T::IndexDeposit` is reserved from
+		/// `new_owner` and the deposit held for the index is returned to the sender. Indices in
+		/// enum sets that a storage migration did not reach yet can not be transferred.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Emits `IndexTransferred` if successful.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - With the `migrate` feature, one storage read of `StorageVersion` and up to one of
+		///   `MigrationCursor`.
+		/// - One storage read and write of the slot.
+		/// - One reserve and up to one unreserve of `T::Currency`.
+		/// - One storage read and write of `Deposits`.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(Module::<T>::TRANSFER_WEIGHT)]
+		fn transfer(origin, new_owner: T::AccountId, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+			ensure!(who != new_owner, Error::<T>::NotTransfer);
+
+			let (set_index, item_index) = Self::split_index(index)
+				.ok_or(Error::<T>::NotOwner)?;
+			ensure!(migration::is_migrated::<T>(set_index), Error::<T>::NotMigrated);
+
+			let owner = Self::enum_slot(set_index, item_index);
+			ensure!(owner.as_ref() == Some(&who), Error::<T>::NotOwner);
+			let deposit = Self::reserve_deposit(&new_owner)?;
+
+			Self::return_deposit(&who, index);
+			<Deposits<T>>::insert(index, deposit);
+			<EnumSlot<T>>::insert(set_index, item_index, &new_owner);
+			Self::forget_index(&who, index);
+			Self::note_index(&new_owner, index);
+
+			Self::deposit_event(RawEvent::IndexTransferred(who, new_owner, index));
+		}
+
+		/// Assign a specific index to `who`, displacing any account that holds it.
+		///
+		/// The enum set of the index is extended with empty slots as needed, but the index can not
+		/// be in an enum set beyond the one being filled, or in one that a storage migration did
+		/// not reach yet. Reserved indices can be assigned as well, and stay reserved: once `who`
+		/// gives one up, it can not be claimed or reclaimed. The deposit held for the index is
+		/// returned to the displaced account, `who` does not pay a deposit.
+		///
+		/// The dispatch origin for this call must be _Root_ or match `T::ForceOrigin`.
+		///
+		/// Emits `IndexFreed` for the displaced account, if any, and `NewAccountIndex`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One storage read of `EnumSetLen` and up to one write.
+		/// - With the `migrate` feature, one storage read of `StorageVersion` and up to one of
+		///   `MigrationCursor`.
+		/// - One storage read and write of the slot.
+		/// - Up to one storage write of `NextEnumSet`.
+		/// - Up to one storage read and write of `LiveIndexCount`.
+		/// - Up to one storage read of `FreeList`, and up to three writes and one of
+		///   `LastFreeIndex` if the index was listed.
+		/// - One storage read and write of `Deposits` and up to one unreserve of `T::Currency`.
+		/// - Up to one storage read and write of `IndexSpaceWarned`.
+		/// - Up to three events.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(Module::<T>::FORCE_SET_INDEX_WEIGHT)]
+		fn force_set_index(origin, who: T::AccountId, index: T::AccountIndex) {
+			T::ForceOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)?;
+
+			let (set_index, item_index) = Self::split_index(index)
+				.ok_or(Error::<T>::NotContiguous)?;
+			ensure!(set_index <= Self::next_enum_set(), Error::<T>::NotContiguous);
+			ensure!(migration::is_migrated::<T>(set_index), Error::<T>::NotMigrated);
+			let mut set_len = Self::enum_set_len(set_index);
+
+			if item_index >= set_len {
+				set_len = item_index + 1;
+				<EnumSetLen<T>>::insert(set_index, set_len);
+			}
+			let old = <EnumSlot<T>>::mutate(set_index, item_index, |slot| {
+				slot.replace(who.clone())
+			});
+			match old.as_ref() {
+				Some(old) => {
+					Self::forget_index(old, index);
+					Self::return_deposit(old, index);
+				}
+				None => Self::note_empty_slot_taken(index),
+			}
+
+			// keep NextEnumSet up to date
+			if set_index == Self::next_enum_set() && set_len == T::EnumSetSize::get() {
+				<NextEnumSet<T>>::put(set_index.saturating_add(One::one()));
+			}
+
+			Self::note_index(&who, index);
+
+			if let Some(old) = old {
+				Self::deposit_event(RawEvent::IndexFreed(old, index));
+			}
+			Self::deposit_event(RawEvent::NewAccountIndex(who, index));
+			Self::note_index_space(index);
+		}
+
+		/// Take an index away from the account that holds it, whether or not that account is alive.
+		///
+		/// The slot is left empty and added to `FreeList`, and the deposit held for the index is
+		/// returned, like for an index given up with `free`. Use `force_set_index` to assign the
+		/// index to a specific account instead. Indices in enum sets that a storage migration did
+		/// not reach yet can not be taken away.
+		///
+		/// The dispatch origin for this call must be _Root_ or match `T::ForceOrigin`.
+		///
+		/// Emits `IndexFreed` if successful.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - With the `migrate` feature, one storage read of `StorageVersion` and up to one of
+		///   `MigrationCursor`.
+		/// - One storage read and write of the slot.
+		/// - One storage read and write of `LiveIndexCount`.
+		/// - One storage read and write of `LastFreeIndex` and up to two writes of `FreeList`.
+		/// - One storage read and up to one write of `LowestReclaimCursor`.
+		/// - One storage read and write of `Deposits` and up to one unreserve of `T::Currency`.
+		/// - One storage read and write of `ReclaimCount`.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(Module::<T>::FORCE_RECLAIM_WEIGHT)]
+		fn force_reclaim(origin, index: T::AccountIndex) {
+			T::ForceOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)?;
+
+			let (set_index, item_index) = Self::split_index(index)
+				.ok_or(Error::<T>::NotAssigned)?;
+			ensure!(migration::is_migrated::<T>(set_index), Error::<T>::NotMigrated);
+			let owner = Self::enum_slot(set_index, item_index)
+				.ok_or(Error::<T>::NotAssigned)?;
+
+			<EnumSlot<T>>::remove(set_index, item_index);
+			<LiveIndexCount<T>>::mutate(|c| *c -= One::one());
+			Self::push_free_index(index);
+			Self::forget_index(&owner, index);
+			Self::return_deposit(&owner, index);
+			Self::note_reclaim(index);
+
+			Self::deposit_event(RawEvent::IndexFreed(owner, index));
+		}
+
+		/// Free the indices of dead accounts, inspecting at most `max` slots.
+		///
+		/// The slots are inspected in ascending order of their index, continuing from where the
+		/// previous call stopped and wrapping around at the last allocated slot. A slot is freed
+		/// if its holder is dead and `T::ReclaimGuard` allows it. Freed indices are added to
+		/// `FreeList`, like for an index given up with `free`, and the deposit held for them
+		/// is returned or slashed according to `T::DeadDepositPolicy`.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Emits `IndexFreed` for each freed index.
+		///
+		/// # <weight>
+		/// - O(max).
+		/// - Up to `max` storage reads of `EnumSetLen` and of the slot.
+		/// - One storage read and write of `ClearCursor`.
+		/// - For each freed index, the writes of `force_reclaim`.
+		/// # </weight>
+		#[weight = WeightForClearDeadIndices::<T>(Default::default())]
+		fn clear_dead_indices(origin, max: u32) {
+			ensure_signed(origin)?;
+
+			let end = Self::allocated_end();
+			if end.is_zero() {
+				return Ok(())
+			}
+			// inspect each slot at most once.
+			let steps = TryInto::<u32>::try_into(end).map_or(max, |end| max.min(end));
+
+			let mut index = Self::clear_cursor();
+			for _ in 0..steps {
+				if index >= end {
+					index = Zero::zero();
+				}
+				let try_index = index;
+				index += One::one();
+
+				// empty slots are reclaimable already.
+				let ((set_index, item_index), who) = match Self::reclaimable_slot(try_index) {
+					Some((slot, Some(who))) => (slot, who),
+					_ => continue,
+				};
+
+				<EnumSlot<T>>::remove(set_index, item_index);
+				<LiveIndexCount<T>>::mutate(|c| *c -= One::one());
+				Self::push_free_index(try_index);
+				Self::forget_index(&who, try_index);
+				Self::release_dead_deposit(&who, try_index);
+				Self::note_reclaim(try_index);
+
+				Self::deposit_event(RawEvent::IndexFreed(who, try_index));
+			}
+
+			<ClearCursor<T>>::put(index);
+		}
 	}
 }
 
@@ -82,36 +655,203 @@ decl_event!(
 		<T as frame_system::Trait>::AccountId,
 		<T as Trait>::AccountIndex
 	{
-		/// A new account index was assigned.
+		/// An account index was assigned by `claim`, by `force_set_index` or to a new account.
 		///
-		/// This event is not triggered when an existing index is reassigned
-		/// to another `AccountId`.
+		/// This includes indices that were freed before, and indices that `claim` or
+		/// `force_set_index` took from another account. If `force_set_index` displaced the holder
+		/// of the index, `IndexFreed` is deposited for it first. This event is not triggered when a
+		/// new account takes the index of a dead account, which deposits `IndexReclaimed` instead,
+		/// or when an index is transferred, which deposits `IndexTransferred`.
 		NewAccountIndex(AccountId, AccountIndex),
+		/// An account index was given up by its holder, forcibly taken away from it, or cleared
+		/// because its holder is dead.
+		IndexFreed(AccountId, AccountIndex),
+		/// An account index was handed from one account to another.
+		IndexTransferred(AccountId, AccountId, AccountIndex),
+		/// An account index held by a dead account was reassigned to a new account. The last
+		/// field is the number of times the index has been reclaimed so far.
+		IndexReclaimed(AccountId, AccountId, AccountIndex, u32),
+		/// Only the given number of indices above the one that was just assigned remain. Deposited
+		/// only once.
+		IndexSpaceNearlyExhausted(AccountIndex),
+		/// No index could be assigned to a new account, as all of them are taken.
+		IndexSpaceExhausted(AccountId),
 	}
 );
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Indices {
+		/// The version of the storage layout.
+		StorageVersion build(|_| migration::CURRENT_VERSION): migration::VersionNumber;
+
 		/// The next free enumeration set.
 		pub NextEnumSet get(fn next_enum_set) build(|config: &GenesisConfig<T>| {
-			(config.ids.len() as u32 / ENUM_SET_SIZE).into()
+			(config.genesis_slots().len() as u32 / T::EnumSetSize::get()).into()
+		}): T::AccountIndex;
+
+		/// The number of allocated slots of each enumeration set.
+		pub EnumSetLen get(fn enum_set_len) build(|config: &GenesisConfig<T>| {
+			let enum_set_size = T::EnumSetSize::get();
+			let slots = config.genesis_slots().len() as u32;
+			(0..(slots + enum_set_size - 1) / enum_set_size)
+				.map(|i| (i.into(), slots.min((i + 1) * enum_set_size) - i * enum_set_size))
+				.collect::<Vec<_>>()
+		}): map T::AccountIndex => u32;
+
+		/// The account held by each allocated slot, keyed by its enumeration set and its position
+		/// within that set. Empty slots, which were freed or are reserved, are `None`.
+		///
+		/// Each slot is stored apart, so reading one index does not decode its whole set.
+		pub EnumSlot get(fn enum_slot) build(|config: &GenesisConfig<T>| {
+			let enum_set_size = T::EnumSetSize::get();
+			config.genesis_slots().into_iter()
+				.enumerate()
+				.filter_map(|(i, who)| {
+					let i = i as u32;
+					Some(((i / enum_set_size).into(), i % enum_set_size, who?))
+				})
+				.collect::<Vec<_>>()
+		}): double_map T::AccountIndex, hasher(blake2_256) u32 => Option<T::AccountId>;
+
+		/// DEPRECATED
+		///
+		/// The enumeration sets, which are now stored slot by slot in `EnumSlot`.
+		#[cfg(any(test, feature = "migrate"))]
+		pub(crate) EnumSet: map T::AccountIndex => Vec<T::AccountId>;
+
+		/// The enum set the migration to v1 continues with.
+		#[cfg(any(test, feature = "migrate"))]
+		pub(crate) MigrationCursor: Option<T::AccountIndex>;
+
+		/// The genesis `reserved` bound. Indices below it are never claimed or reclaimed, whether
+		/// or not they were assigned at genesis, and stay reserved once assigned by force.
+		pub ReservedUpTo get(fn reserved_up_to) build(|config: &GenesisConfig<T>| {
+			config.reserved
+		}): T::AccountIndex;
+
+		/// The number of enum set slots that hold an account. Slots held by dead accounts are
+		/// counted until they are reclaimed.
+		pub LiveIndexCount get(fn live_index_count) build(|config: &GenesisConfig<T>| {
+			((config.ids.len() + config.dead_ids.len()) as u32).into()
 		}): T::AccountIndex;
 
-		/// The enumeration sets.
-		pub EnumSet get(fn enum_set) build(|config: &GenesisConfig<T>| {
-			(0..((config.ids.len() as u32) + ENUM_SET_SIZE - 1) / ENUM_SET_SIZE)
-				.map(|i| (
-					i.into(),
-					config.ids[
-						(i * ENUM_SET_SIZE) as usize..
-						config.ids.len().min(((i + 1) * ENUM_SET_SIZE) as usize)
-					].to_owned(),
-				))
+		/// Indices that were given up by their holder, as a list linked from the most recently
+		/// freed index in `LastFreeIndex` back to the first one.
+		///
+		/// New accounts take these before other reclaimable indices. An entry is removed once its
+		/// slot is assigned again, by whichever call does so. Each entry is stored apart, so
+		/// adding, taking or removing one does not decode the others.
+		pub FreeList get(fn free_list_entry):
+			map T::AccountIndex => Option<FreeListEntry<T::AccountIndex>>;
+
+		/// The most recently freed index in `FreeList`.
+		pub LastFreeIndex get(fn last_free_index): Option<T::AccountIndex>;
+
+		/// Whether `IndexSpaceNearlyExhausted` was deposited.
+		pub IndexSpaceWarned get(fn index_space_warned): bool;
+
+		/// The index from which new accounts continue to look for a reclaimable index.
+		ReclaimCursor get(fn reclaim_cursor): T::AccountIndex;
+
+		/// The index from which new accounts look for the lowest reclaimable index under
+		/// `ReclaimPolicy::LowestFirst`. Every index below it was last seen held by a live account.
+		LowestReclaimCursor get(fn lowest_reclaim_cursor): T::AccountIndex;
+
+		/// The index from which `clear_dead_indices` continues to look for indices of dead
+		/// accounts.
+		pub ClearCursor get(fn clear_cursor): T::AccountIndex;
+
+		/// The number of times an index was taken from a dead account by a new account or by
+		/// `clear_dead_indices`, or from its holder by `force_reclaim`.
+		pub ReclaimCount get(fn reclaim_count): map T::AccountIndex => u32;
+
+		/// The deposit reserved from the holder of an index.
+		///
+		/// Indices assigned to new accounts, at genesis or by force do not hold a deposit.
+		pub Deposits get(fn deposit_of): map T::AccountIndex => BalanceOf<T>;
+
+		/// The reverse lookup from an account to one of the indices it holds.
+		///
+		/// Indices in enum sets that a storage migration did not reach yet are not included.
+		pub Accounts get(fn index_of) build(|config: &GenesisConfig<T>| {
+			config.genesis_slots().into_iter()
+				.enumerate()
+				.filter_map(|(i, who)| Some((who?, (i as u32).into())))
+				.collect::<Vec<_>>()
+		}): map T::AccountId => Option<T::AccountIndex>;
+
+		/// The indices held by each account, keyed by the account and then the index itself.
+		///
+		/// When the index in `Accounts` is given up, the reverse lookup moves to another one of
+		/// these.
+		HeldIndices build(|config: &GenesisConfig<T>| {
+			config.genesis_slots().into_iter()
+				.enumerate()
+				.filter_map(|(i, who)| Some((who?, (i as u32).into(), (i as u32).into())))
 				.collect::<Vec<_>>()
-		}): map T::AccountIndex => Vec<T::AccountId>;
+		}): double_map T::AccountId, hasher(blake2_256) T::AccountIndex => Option<T::AccountIndex>;
 	}
 	add_extra_genesis {
 		config(ids): Vec<T::AccountId>;
+		/// Indices below this bound are not handed out to new accounts. Indices that are not
+		/// assigned to one of the `ids` are left empty.
+		config(reserved): T::AccountIndex;
+		/// Accounts placed at the given indices, after the `ids`. These are meant to be accounts
+		/// that `T::IsDeadAccount` reports as dead, so that their indices can be reclaimed right
+		/// away. Slots between them that are above `reserved` are left empty and can be claimed.
+		config(dead_ids): Vec<(T::AccountIndex, T::AccountId)>;
+		build(|config: &GenesisConfig<T>| {
+			let mut ids = sp_std::collections::btree_set::BTreeSet::new();
+			for who in config.ids.iter().chain(config.dead_ids.iter().map(|(_, who)| who)) {
+				assert!(ids.insert(who), "Account {:?} appears twice in the genesis ids.", who);
+			}
+
+			// new accounts are appended to the set at `NextEnumSet`, so it has to be the first set
+			// that is not full. It is derived from the config here, apart from `genesis_slots`.
+			let to_usize = |index: T::AccountIndex| {
+				TryInto::<usize>::try_into(index).ok().unwrap_or(usize::max_value())
+			};
+			let slots = config.dead_ids.iter()
+				.map(|(index, _)| to_usize(*index).saturating_add(1))
+				.fold(config.ids.len().max(to_usize(config.reserved)), usize::max);
+			let full_sets = slots / T::EnumSetSize::get() as usize;
+			assert_eq!(
+				<NextEnumSet<T>>::get(),
+				(full_sets as u32).into(),
+				"Genesis NextEnumSet does not match the number of full enum sets.",
+			);
+		});
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: Trait> GenesisConfig<T> {
+	/// The content of all enum set slots at genesis: the `ids`, padded with empty slots up to the
+	/// `reserved` bound, and the `dead_ids` at their indices.
+	///
+	/// Every genesis storage item is built from these, so this also checks `T::EnumSetSize`. The
+	/// index of a dead account must be less than an enum set beyond all other slots, so the slots
+	/// in between are bounded.
+	fn genesis_slots(&self) -> Vec<Option<T::AccountId>> {
+		assert!(T::EnumSetSize::get() > 0, "The enum set size must not be zero.");
+		let reserved = self.reserved.try_into().ok().unwrap_or(usize::max_value());
+		let mut slots = self.ids.iter().cloned().map(Some).collect::<Vec<_>>();
+		if slots.len() < reserved {
+			slots.resize(reserved, None);
+		}
+		let end = slots.len()
+			.saturating_add(self.dead_ids.len())
+			.saturating_add(T::EnumSetSize::get() as usize);
+		for (index, who) in &self.dead_ids {
+			let i = (*index).try_into().ok().unwrap_or(usize::max_value());
+			assert!(i < end, "Genesis index {:?} is too far beyond the other indices.", index);
+			if slots.len() <= i {
+				slots.resize(i + 1, None);
+			}
+			assert!(slots[i].is_none(), "Genesis index {:?} is assigned twice.", index);
+			slots[i] = Some(who.clone());
+		}
+		slots
 	}
 }
 
@@ -119,23 +859,211 @@ impl<T: Trait> Module<T> {
 	// PUBLIC IMMUTABLES
 
 	/// Lookup an T::AccountIndex to get an Id, if there's one there.
+	///
+	/// Within `with_lookup_cache`, the result is memoized.
 	pub fn lookup_index(index: T::AccountIndex) -> Option<T::AccountId> {
-		let enum_set_size = Self::enum_set_size();
-		let set = Self::enum_set(index / enum_set_size);
-		let i: usize = (index % enum_set_size).try_into().ok()?;
-		set.get(i).cloned()
+		cache::cached(&index, || Self::try_lookup_index(index).ok())
+	}
+
+	/// Run `f` with an in-memory cache of `lookup_index` results, like for validating a
+	/// transaction.
+	///
+	/// Cached results are dropped whenever this module writes the slot of their index. Other
+	/// writes of the slots, like by a storage migration, must not happen within `f`. Nested calls
+	/// use the cache of the outermost call.
+	pub fn with_lookup_cache<R>(f: impl FnOnce() -> R) -> R {
+		cache::with_lookup_cache(f)
+	}
+
+	/// Lookup an T::AccountIndex to get an Id, or the reason why there is none.
+	///
+	/// Indices that a storage migration did not reach yet are looked up in the previous storage
+	/// layout.
+	pub fn try_lookup_index(index: T::AccountIndex) -> Result<T::AccountId, LookupIndexError> {
+		let (set_index, i) = Self::split_index(index).ok_or(LookupIndexError::OutOfRange)?;
+		if !migration::is_index_migrated::<T>(index) {
+			return migration::lookup_v0_index::<T>(index)
+		}
+		// the set at `NextEnumSet` is the one being filled.
+		if set_index > Self::next_enum_set() {
+			return Err(LookupIndexError::OutOfRange)
+		}
+		Self::enum_slot(set_index, i).ok_or(LookupIndexError::Unassigned)
+	}
+
+	/// Lookup several indices at once. The result is in the same order as `indices`.
+	pub fn lookup_indices(indices: &[T::AccountIndex]) -> Vec<Option<T::AccountId>> {
+		indices.iter().map(|&index| Self::lookup_index(index)).collect()
+	}
+
+	/// Lookup `count` consecutive indices starting at `start`, each paired with its account.
+	///
+	/// At most `MAX_LOOKUP_RANGE` indices are returned, fewer if the index space ends before.
+	/// `EnumSetLen` is read once per enumeration set, so slots that were not allocated yet are not
+	/// read. This is meant for paginated RPC queries.
+	pub fn lookup_range(
+		start: T::AccountIndex,
+		count: u32,
+	) -> Vec<(T::AccountIndex, Option<T::AccountId>)> {
+		let count = count.min(Self::MAX_LOOKUP_RANGE);
+		let next_set_index = Self::next_enum_set();
+
+		let mut result = Vec::with_capacity(count as usize);
+		let mut current_set = None;
+		let mut index = start;
+		for _ in 0..count {
+			let who = Self::split_index(index)
+				.filter(|(set_index, _)| *set_index <= next_set_index)
+				.and_then(|(set_index, i)| {
+					let set_len = match current_set {
+						Some((current, set_len)) if current == set_index => set_len,
+						_ => {
+							let set_len = Self::enum_set_len(set_index);
+							current_set = Some((set_index, set_len));
+							set_len
+						}
+					};
+					if i < set_len { Self::enum_slot(set_index, i) } else { None }
+				});
+			result.push((index, who));
+
+			index = match index.checked_add(&One::one()) {
+				Some(next) => next,
+				None => break,
+			};
+		}
+		result
+	}
+
+	/// The enum set of `index` and its position within that set, which together are the key of
+	/// the slot of `index` in `EnumSlot`.
+	pub fn index_location(index: T::AccountIndex) -> (T::AccountIndex, u32) {
+		Self::split_index(index)
+			.expect("The position is less than `T::EnumSetSize`, which is a `u32`; qed")
+	}
+
+	/// `true` if the account `index` is currently assigned to an account.
+	///
+	/// This only checks whether the slot exists, without decoding the account, unless the index
+	/// is not migrated yet.
+	pub fn is_index_assigned(index: T::AccountIndex) -> bool {
+		match Self::split_index(index) {
+			Some((set_index, i)) if migration::is_index_migrated::<T>(index) => {
+				<EnumSlot<T>>::exists(set_index, i)
+			}
+			Some(_) => migration::lookup_v0_index::<T>(index).is_ok(),
+			None => false,
+		}
+	}
+
+	/// `true` if `index` is below the genesis `reserved` bound, see `ReservedUpTo`.
+	pub fn is_reserved(index: T::AccountIndex) -> bool {
+		index < Self::reserved_up_to()
 	}
 
 	/// `true` if the account `index` is ready for reclaim.
 	pub fn can_reclaim(try_index: T::AccountIndex) -> bool {
-		let enum_set_size = Self::enum_set_size();
-		let try_set = Self::enum_set(try_index / enum_set_size);
-		let maybe_usize: Result<usize, _> = (try_index % enum_set_size).try_into();
-		if let Ok(i) = maybe_usize {
-			i < try_set.len() && T::IsDeadAccount::is_dead_account(&try_set[i])
-		} else {
-			false
+		Self::reclaimable_slot(try_index).is_some()
+	}
+
+	/// `can_reclaim` of each of `indices`, in the same order.
+	///
+	/// `EnumSetLen` is read once per enumeration set, so this is cheaper than calling
+	/// `can_reclaim` for each index.
+	pub fn can_reclaim_batch(indices: &[T::AccountIndex]) -> Vec<bool> {
+		let mut set_lens = BTreeMap::new();
+		indices.iter()
+			.map(|&index| match Self::split_index(index) {
+				Some((set_index, i)) => {
+					let set_len = *set_lens.entry(set_index)
+						.or_insert_with(|| Self::enum_set_len(set_index));
+					i < set_len && {
+						let who = Self::enum_slot(set_index, i);
+						Self::is_reclaimable(index, who.as_ref())
+					}
+				}
+				None => false,
+			})
+			.collect()
+	}
+
+	/// Give `who` an index like a new account, unless it already holds one, and return its index.
+	///
+	/// Meant to set up tests of dependent modules. Panics if there is no index left.
+	#[cfg(any(test, feature = "std"))]
+	pub fn ensure_indexed(who: &T::AccountId) -> T::AccountIndex {
+		if let Some(index) = Self::index_of(who) {
+			return index
 		}
+		<Self as OnNewAccount<_>>::on_new_account(who);
+		Self::index_of(who).expect("No index is left for a new account")
+	}
+
+	/// Place `accounts` in the slots of the enumeration set `set_index`, replacing its content.
+	///
+	/// `NextEnumSet` moves past the set if it is filled, like for `force_set_index`. No deposits
+	/// are reserved or returned. Meant to set up tests of dependent modules, which need the
+	/// `test-helpers` feature. Panics if there are more accounts than `T::EnumSetSize` or if the
+	/// set is beyond `NextEnumSet`.
+	#[cfg(any(test, feature = "test-helpers"))]
+	pub fn set_enum_set_for_test(set_index: T::AccountIndex, accounts: Vec<T::AccountId>) {
+		let set_len = accounts.len() as u32;
+		assert!(set_len <= T::EnumSetSize::get(), "More accounts than fit into an enum set");
+		assert!(set_index <= Self::next_enum_set(), "The enum set is beyond the one being filled");
+		let index_of = |i| Self::join_index(set_index, i).expect("The enum set is out of range");
+
+		for i in 0..Self::enum_set_len(set_index) {
+			if let Some(old) = <EnumSlot<T>>::take(set_index, i) {
+				Self::forget_index(&old, index_of(i));
+				<LiveIndexCount<T>>::mutate(|c| *c -= One::one());
+			}
+		}
+		for (i, who) in (0..).zip(accounts) {
+			<EnumSlot<T>>::insert(set_index, i, &who);
+			Self::note_index(&who, index_of(i));
+			<LiveIndexCount<T>>::mutate(|c| *c += One::one());
+		}
+		<EnumSetLen<T>>::insert(set_index, set_len);
+
+		if set_index == Self::next_enum_set() && set_len == T::EnumSetSize::get() {
+			<NextEnumSet<T>>::put(set_index.saturating_add(One::one()));
+		}
+	}
+
+	/// The slots of the enumeration set `set_index`, empty ones being `None`.
+	///
+	/// This reads every allocated slot of the set.
+	pub fn enum_set(set_index: T::AccountIndex) -> Vec<Option<T::AccountId>> {
+		(0..Self::enum_set_len(set_index)).map(|i| Self::enum_slot(set_index, i)).collect()
+	}
+
+	/// The number of slots of each enumeration set.
+	///
+	/// The account of `index` is stored in `EnumSlot` under `index / size` and `index % size`.
+	pub fn enum_set_size_hint() -> T::AccountIndex {
+		Self::enum_set_size()
+	}
+
+	/// Iterate over all assigned indices and the accounts holding them, in ascending order of the
+	/// index.
+	///
+	/// Indices held by dead accounts are included, empty slots are not. This is O(total indices)
+	/// and reads every enum set slot, so it is meant for off-chain and RPC use only, not for use
+	/// in dispatchables.
+	pub fn enumerate() -> impl Iterator<Item = (T::AccountIndex, T::AccountId)> {
+		let last_set = Self::next_enum_set();
+
+		let set_indices = iter::successors(Some(Zero::zero()), move |&i| {
+			if i < last_set { Some(i + One::one()) } else { None }
+		});
+
+		set_indices
+			.flat_map(move |set_index: T::AccountIndex| {
+				(0..Self::enum_set_len(set_index)).filter_map(move |i| {
+					let who = Self::enum_slot(set_index, i)?;
+					Some((Self::join_index(set_index, i)?, who))
+				})
+			})
 	}
 
 	/// Lookup an address to get an Id, if there's one there.
@@ -148,10 +1076,342 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Lookup a borrowed address to get an Id, if there's one there.
+	pub fn lookup_address_ref(
+		a: &address::Address<T::AccountId, T::AccountIndex>
+	) -> Option<T::AccountId> {
+		match a {
+			address::Address::Id(i) => Some(i.clone()),
+			address::Address::Index(i) => Self::lookup_index(*i),
+		}
+	}
+
+	/// The address referring to `index`.
+	pub fn address_for_index(index: T::AccountIndex) -> Address<T> {
+		address::Address::Index(index)
+	}
+
+	/// The address referring to `id`.
+	pub fn address_for_id(id: T::AccountId) -> Address<T> {
+		address::Address::Id(id)
+	}
+
+	/// The shortest address referring to `who`: its index if it has one, its id otherwise.
+	pub fn compact_address(who: &T::AccountId) -> Address<T> {
+		match Self::index_of(who) {
+			Some(index) => address::Address::Index(index),
+			None => address::Address::Id(who.clone()),
+		}
+	}
+
 	// PUBLIC MUTABLES (DANGEROUS)
 
 	fn enum_set_size() -> T::AccountIndex {
-		ENUM_SET_SIZE.into()
+		T::EnumSetSize::get().into()
+	}
+
+	/// The enum set of `index` and the position of `index` within that set.
+	fn split_index(index: T::AccountIndex) -> Option<(T::AccountIndex, u32)> {
+		let enum_set_size = Self::enum_set_size();
+		let item_index = TryInto::<u32>::try_into(index % enum_set_size).ok()?;
+		Some((index / enum_set_size, item_index))
+	}
+
+	/// The index of the slot at `item_index` within the enum set `set_index`.
+	///
+	/// Returns `None` if the index does not fit into `T::AccountIndex`.
+	fn join_index(set_index: T::AccountIndex, item_index: u32) -> Option<T::AccountIndex> {
+		set_index.checked_mul(&Self::enum_set_size())?
+			.checked_add(&T::AccountIndex::from(item_index))
+	}
+
+	/// Record that `who` holds `index`, and make it the reverse lookup of `who` unless `who`
+	/// already has one.
+	///
+	/// Every write of a slot is paired with `note_index` or `forget_index`, so both also drop the
+	/// cached lookup of `index`.
+	fn note_index(who: &T::AccountId, index: T::AccountIndex) {
+		cache::forget(&index);
+		<HeldIndices<T>>::insert(who, index, index);
+		if !<Accounts<T>>::exists(who) {
+			<Accounts<T>>::insert(who, index);
+		}
+	}
+
+	/// Record that `who` gave up `index`. If it was the reverse lookup of `who`, point the reverse
+	/// lookup to another index `who` holds, if there is one.
+	fn forget_index(who: &T::AccountId, index: T::AccountIndex) {
+		cache::forget(&index);
+		<HeldIndices<T>>::remove(who, index);
+		if Self::index_of(who) == Some(index) {
+			match <HeldIndices<T>>::iter_prefix(who).next() {
+				Some(other) => <Accounts<T>>::insert(who, other),
+				None => <Accounts<T>>::remove(who),
+			}
+		}
+	}
+
+	/// Count the previously empty slot at `index` as live and drop it from `FreeList`.
+	///
+	/// Empty slots are only listed in `FreeList` while they stay empty, so the list does not
+	/// grow however often an index is freed and taken again.
+	fn note_empty_slot_taken(index: T::AccountIndex) {
+		<LiveIndexCount<T>>::mutate(|c| *c += One::one());
+		Self::remove_free_index(index);
+	}
+
+	/// Add `index` to the end of `FreeList`, unless it is listed already.
+	///
+	/// `LowestReclaimCursor` is moved back to `index` if it is above it.
+	fn push_free_index(index: T::AccountIndex) {
+		if index < Self::lowest_reclaim_cursor() {
+			<LowestReclaimCursor<T>>::put(index);
+		}
+		if <FreeList<T>>::exists(index) {
+			return
+		}
+		let last = Self::last_free_index();
+		if let Some(last) = last {
+			<FreeList<T>>::mutate(last, |entry| {
+				if let Some(entry) = entry {
+					entry.newer = Some(index);
+				}
+			});
+		}
+		<FreeList<T>>::insert(index, FreeListEntry { older: last, newer: None });
+		<LastFreeIndex<T>>::put(index);
+	}
+
+	/// Remove `index` from `FreeList`, linking its neighbours to each other.
+	fn remove_free_index(index: T::AccountIndex) {
+		let entry = match <FreeList<T>>::take(index) {
+			Some(entry) => entry,
+			None => return,
+		};
+		if let Some(older) = entry.older {
+			<FreeList<T>>::mutate(older, |older| {
+				if let Some(older) = older {
+					older.newer = entry.newer;
+				}
+			});
+		}
+		match entry.newer {
+			Some(newer) => <FreeList<T>>::mutate(newer, |newer| {
+				if let Some(newer) = newer {
+					newer.older = entry.older;
+				}
+			}),
+			None => match entry.older {
+				Some(older) => <LastFreeIndex<T>>::put(older),
+				None => <LastFreeIndex<T>>::kill(),
+			},
+		}
+	}
+
+	/// The indices in `FreeList`, most recently freed last.
+	///
+	/// This reads every entry of the list.
+	pub fn free_indices() -> Vec<T::AccountIndex> {
+		let mut free_indices = iter::successors(Self::last_free_index(), |index| {
+			Self::free_list_entry(*index).and_then(|entry| entry.older)
+		}).collect::<Vec<_>>();
+		free_indices.reverse();
+		free_indices
+	}
+
+	/// Deposit `IndexSpaceNearlyExhausted` the first time `index` leaves at most
+	/// `T::IndexSpaceWarningThreshold` indices above it.
+	///
+	/// `claim` and `force_set_index` can skip over the index at the threshold, so the warning is
+	/// remembered in `IndexSpaceWarned` instead of relying on the order of assignment.
+	fn note_index_space(index: T::AccountIndex) {
+		let remaining = T::AccountIndex::max_value() - index;
+		if remaining <= T::IndexSpaceWarningThreshold::get() && !Self::index_space_warned() {
+			IndexSpaceWarned::put(true);
+			Self::deposit_event(RawEvent::IndexSpaceNearlyExhausted(remaining));
+		}
+	}
+
+	/// Reserve `T::IndexDeposit` from `who` and return the reserved amount.
+	fn reserve_deposit(who: &T::AccountId) -> Result<BalanceOf<T>, DispatchError> {
+		let deposit = T::IndexDeposit::get();
+		T::Currency::reserve(who, deposit)?;
+		Ok(deposit)
+	}
+
+	/// Return the deposit held for `index` to its holder `who`.
+	fn return_deposit(who: &T::AccountId, index: T::AccountIndex) {
+		let deposit = <Deposits<T>>::take(index);
+		if !deposit.is_zero() {
+			T::Currency::unreserve(who, deposit);
+		}
+	}
+
+	/// Handle the deposit held for `index` by the dead account `who` according to
+	/// `T::DeadDepositPolicy`.
+	fn release_dead_deposit(who: &T::AccountId, index: T::AccountIndex) {
+		let deposit = <Deposits<T>>::take(index);
+		if deposit.is_zero() {
+			return
+		}
+		match T::DeadDepositPolicy::get() {
+			DepositPolicy::Return => {
+				T::Currency::unreserve(who, deposit);
+			}
+			DepositPolicy::Slash => {
+				let (imbalance, _) = T::Currency::slash_reserved(who, deposit);
+				T::Slashed::on_unbalanced(imbalance);
+			}
+		}
+	}
+
+	/// Increment the `ReclaimCount` of `index` and return the new count.
+	fn note_reclaim(index: T::AccountIndex) -> u32 {
+		<ReclaimCount<T>>::mutate(index, |count| {
+			*count = count.saturating_add(1);
+			*count
+		})
+	}
+
+	/// `true` if the slot at `index`, currently holding `who`, may be handed to another account.
+	///
+	/// Reserved indices never are, even once their holder freed them or died. A dead holder is
+	/// only replaced if `T::ReclaimGuard` allows it.
+	fn is_reclaimable(index: T::AccountIndex, who: Option<&T::AccountId>) -> bool {
+		!Self::is_reserved(index) && who.map_or(true, |who| {
+			T::IsDeadAccount::is_dead_account(who) && T::ReclaimGuard::can_reclaim(who, index)
+		})
+	}
+
+	/// The position of the slot at `index` and the account it holds, if the slot is allocated
+	/// and may be handed to another account.
+	fn reclaimable_slot(
+		index: T::AccountIndex,
+	) -> Option<((T::AccountIndex, u32), Option<T::AccountId>)> {
+		let (set_index, item_index) = Self::split_index(index)?;
+		if item_index >= Self::enum_set_len(set_index) {
+			return None
+		}
+		let who = Self::enum_slot(set_index, item_index);
+		if Self::is_reclaimable(index, who.as_ref()) {
+			Some(((set_index, item_index), who))
+		} else {
+			None
+		}
+	}
+
+	/// Pop `FreeList` until a reclaimable index is found, inspecting at most `steps` entries.
+	///
+	/// `steps` is reduced by the number of inspected entries.
+	fn pop_free_index(
+		steps: &mut u32,
+	) -> Option<(T::AccountIndex, (T::AccountIndex, u32), Option<T::AccountId>)> {
+		let mut found = None;
+		while *steps > 0 && found.is_none() {
+			let index = Self::last_free_index()?;
+			Self::remove_free_index(index);
+			*steps -= 1;
+			found = Self::reclaimable_slot(index).map(|(slot, old)| (index, slot, old));
+		}
+		found
+	}
+
+	/// Look for the lowest reclaimable index, inspecting at most `steps` slots from
+	/// `LowestReclaimCursor` on.
+	///
+	/// Reserved indices are skipped without being inspected. The cursor is moved past the
+	/// inspected slots, including the one that is found, as that is taken by a live account.
+	fn lowest_reclaimable(
+		steps: u32,
+	) -> Option<(T::AccountIndex, (T::AccountIndex, u32), Option<T::AccountId>)> {
+		let end = Self::allocated_end();
+		let mut index = Self::lowest_reclaim_cursor().max(Self::reserved_up_to());
+		let mut found = None;
+		for _ in 0..steps {
+			if index >= end {
+				break
+			}
+			let try_index = index;
+			index += One::one();
+			if let Some((slot, old)) = Self::reclaimable_slot(try_index) {
+				found = Some((try_index, slot, old));
+				break
+			}
+		}
+
+		<LowestReclaimCursor<T>>::put(index);
+		found
+	}
+
+	/// Look for a reclaimable index, inspecting at most `steps` slots in ascending order of their
+	/// index.
+	///
+	/// The search continues from where the previous one stopped and wraps around at the last
+	/// allocated slot.
+	fn scan_reclaimable(
+		steps: u32,
+	) -> Option<(T::AccountIndex, (T::AccountIndex, u32), Option<T::AccountId>)> {
+		let end = Self::allocated_end();
+		if end.is_zero() {
+			return None
+		}
+		// inspect each slot at most once.
+		let steps = TryInto::<u32>::try_into(end).map_or(steps, |end| steps.min(end));
+
+		let mut index = Self::reclaim_cursor();
+		let mut found = None;
+		for _ in 0..steps {
+			if index >= end {
+				index = Zero::zero();
+			}
+			let try_index = index;
+			index += One::one();
+			if let Some((slot, old)) = Self::reclaimable_slot(try_index) {
+				found = Some((try_index, slot, old));
+				break
+			}
+		}
+
+		<ReclaimCursor<T>>::put(index);
+		found
+	}
+
+	/// The index after the last allocated slot of the set at `NextEnumSet`.
+	fn allocated_end() -> T::AccountIndex {
+		let next_set_index = Self::next_enum_set();
+		next_set_index.saturating_mul(Self::enum_set_size())
+			.saturating_add(T::AccountIndex::from(Self::enum_set_len(next_set_index)))
+	}
+
+	/// Hand the slot at `index` to `who`, replacing `old`, and deposit the matching event.
+	fn reclaim(
+		who: &T::AccountId,
+		index: T::AccountIndex,
+		(set_index, item_index): (T::AccountIndex, u32),
+		old: Option<T::AccountId>,
+	) {
+		match old.as_ref() {
+			Some(old) => {
+				Self::forget_index(old, index);
+				Self::release_dead_deposit(old, index);
+			}
+			None => Self::note_empty_slot_taken(index),
+		}
+		<EnumSlot<T>>::insert(set_index, item_index, who);
+		Self::note_index(who, index);
+
+		Self::deposit_event(match old {
+			Some(old) => {
+				let count = Self::note_reclaim(index);
+				RawEvent::IndexReclaimed(old, who.clone(), index, count)
+			}
+			None => RawEvent::NewAccountIndex(who.clone(), index),
+		});
+	}
+
+	/// Ensures storage is upgraded to most recent necessary state.
+	fn ensure_storage_upgraded() {
+		migration::perform_migrations::<T>();
 	}
 }
 
@@ -159,65 +1419,138 @@ impl<T: Trait> OnNewAccount<T::AccountId> for Module<T> {
 	// Implementation of the config type managing the creation of new accounts.
 	// See Balances module for a concrete example.
 	//
+	// If every index is taken, the account is left without one and `IndexSpaceExhausted` is
+	// deposited. It is also left without one while the storage of the enum set being filled is not
+	// migrated, which is only the case until the migration from v0 reaches it.
+	//
 	// # <weight>
 	// - Independent of the arguments.
 	// - Given the correct value of `Self::next_enum_set`, it always has a limited
 	//   number of reads and writes and no complex computation.
+	// - Up to `T::MaxReclaimSteps` reads of enum set slots or entries of `FreeList`, up to three
+	//   writes of `FreeList` and one of `LastFreeIndex` for each popped entry, and as many for
+	//   the entry of the taken index. With `ReclaimPolicy::LowestFirst`, up to
+	//   `T::MaxReclaimSteps` reads of enum set slots and as many of entries of `FreeList`, and
+	//   one read and write of `LowestReclaimCursor`.
+	// - Up to one read and write of `ReclaimCount`.
+	// - Up to one read and write of `IndexSpaceWarned`.
+	// - Up to one read and write of `Deposits` and one unreserve or slash of `T::Currency`.
 	//
-	// As for storage, calling this function with _non-dead-indices_ will linearly grow the length of
-	// of `Self::enum_set`. Appropriate economic incentives should exist to make callers of this
-	// function provide a `who` argument that reclaims a dead account.
+	// As for storage, calling this function with _non-dead-indices_ will linearly grow the number
+	// of enum set slots unless a reclaimable index is found. Appropriate economic incentives
+	// should exist to make callers of this function provide a `who` argument that reclaims a dead
+	// account.
 	//
 	// At the time of this writing, only the Balances module calls this function upon creation
 	// of new accounts.
 	// # </weight>
 	fn on_new_account(who: &T::AccountId) {
-		let enum_set_size = Self::enum_set_size();
 		let next_set_index = Self::next_enum_set();
+		if !migration::is_migrated::<T>(next_set_index) {
+			native::trace!(
+				target: "indices",
+				"New account {:?} is not indexed before the storage migration",
+				who,
+			);
+			return
+		}
 
-		if let Some(try_index) = T::ResolveHint::resolve_hint(who) {
-			// then check to see if this account id identifies a dead account index.
-			let set_index = try_index / enum_set_size;
-			let mut try_set = Self::enum_set(set_index);
-			if let Ok(item_index) = (try_index % enum_set_size).try_into() {
-				if item_index < try_set.len() {
-					if T::IsDeadAccount::is_dead_account(&try_set[item_index]) {
-						// yup - this index refers to a dead account. can be reused.
-						try_set[item_index] = who.clone();
-						<EnumSet<T>>::insert(set_index, try_set);
-
-						return
-					}
-				}
+		// reuse the index of a dead account or a freed index, if there is one.
+		let hint = T::ResolveHint::resolve_hint(who);
+		let hinted = || {
+			let try_index = hint?;
+			let found = Self::reclaimable_slot(try_index).map(|(slot, old)| (try_index, slot, old));
+			if found.is_none() {
+				native::trace!(
+					target: "indices",
+					"Hinted index {:?} of new account {:?} is not reclaimable",
+					try_index,
+					who,
+				);
+			}
+			found
+		};
+		let mut steps = T::MaxReclaimSteps::get();
+		let found = match T::ReclaimPolicy::get() {
+			ReclaimPolicy::PreferHint => hinted()
+				.or_else(|| Self::pop_free_index(&mut steps))
+				.or_else(|| Self::scan_reclaimable(steps)),
+			ReclaimPolicy::PreferFreeList => Self::pop_free_index(&mut steps)
+				.or_else(hinted)
+				.or_else(|| Self::scan_reclaimable(steps)),
+			ReclaimPolicy::LowestFirst => Self::lowest_reclaimable(steps)
+				.or_else(hinted)
+				.or_else(|| Self::pop_free_index(&mut steps)),
+		};
+		if let Some((index, slot, old)) = found {
+			if Some(index) == hint {
+				native::trace!(
+					target: "indices",
+					"New account {:?} reclaims its hinted index {:?} from {:?}",
+					who,
+					index,
+					old,
+				);
+			} else {
+				native::trace!(
+					target: "indices",
+					"New account {:?} reclaims index {:?} from {:?}",
+					who,
+					index,
+					old,
+				);
 			}
+			Self::reclaim(who, index, slot, old);
+			return
 		}
 
 		// insert normally as a back up
 		let mut set_index = next_set_index;
 		// defensive only: this loop should never iterate since we keep NextEnumSet up to date
 		// later.
-		let mut set = loop {
-			let set = Self::enum_set(set_index);
-			if set.len() < ENUM_SET_SIZE as usize {
-				break set;
+		let set_len = loop {
+			let set_len = Self::enum_set_len(set_index);
+			if set_len < T::EnumSetSize::get() {
+				break Some(set_len);
+			}
+			match set_index.checked_add(&One::one()) {
+				Some(next) => set_index = next,
+				None => break None,
 			}
-			set_index += One::one();
 		};
 
-		let index = set_index * enum_set_size + T::AccountIndex::from(set.len() as u32);
-
-		// update set.
-		set.push(who.clone());
+		let slot = set_len
+			.and_then(|set_len| Some((set_len, Self::join_index(set_index, set_len)?)));
+		let (set_len, index) = match slot {
+			Some(slot) => slot,
+			None => {
+				// every index is taken, so the account is left without one.
+				native::trace!(target: "indices", "No index is left for new account {:?}", who);
+				Self::deposit_event(RawEvent::IndexSpaceExhausted(who.clone()));
+				return
+			}
+		};
 
 		// keep NextEnumSet up to date
-		if set.len() == ENUM_SET_SIZE as usize {
-			<NextEnumSet<T>>::put(set_index + One::one());
+		if set_len + 1 == T::EnumSetSize::get() {
+			<NextEnumSet<T>>::put(set_index.saturating_add(One::one()));
 		}
 
-		// write set.
-		<EnumSet<T>>::insert(set_index, set);
+		// write slot.
+		native::trace!(
+			target: "indices",
+			"New account {:?} is given the new index {:?}",
+			who,
+			index,
+		);
+		<EnumSetLen<T>>::insert(set_index, set_len + 1);
+		<EnumSlot<T>>::insert(set_index, set_len, who);
+		<LiveIndexCount<T>>::mutate(|c| *c += One::one());
+		Self::note_index(who, index);
 
 		Self::deposit_event(RawEvent::NewAccountIndex(who.clone(), index));
+
+		Self::note_index_space(index);
 	}
 }
 
@@ -229,7 +1562,11 @@ impl<T: Trait> StaticLookup for Module<T> {
 		Self::lookup_address(a).ok_or(LookupError)
 	}
 
+	/// Returns the index of `a` if it has one, see [`Module::compact_address`].
+	///
+	/// This reads the storage, so it must be called with externalities, like in the runtime or in
+	/// a genesis build.
 	fn unlookup(a: Self::Target) -> Self::Source {
-		address::Address::Id(a)
+		Self::compact_address(&a)
 	}
 }
@@ -0,0 +1,198 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage migrations for pallet-indices.
+
+/// Indicator of a version of a storage layout.
+pub type VersionNumber = u32;
+
+// the current expected version of the storage
+pub const CURRENT_VERSION: VersionNumber = 1;
+
+#[cfg(any(test, feature = "migrate"))]
+mod inner {
+	use crate::{Store, Module, Trait, LookupIndexError};
+	use sp_std::convert::TryInto;
+	use frame_support::{StorageMap, StorageDoubleMap, StorageValue, traits::Get};
+	use sp_runtime::traits::{One, Bounded, CheckedAdd, CheckedMul, CheckedSub, Saturating};
+	use super::{CURRENT_VERSION, VersionNumber};
+
+	// the number of accounts per enum set of the v0 layout, which did not have `T::EnumSetSize`.
+	const V0_ENUM_SET_SIZE: u32 = 64;
+
+	// the maximum number of enum sets `to_v1` migrates in one block. A set of the v0 layout holds
+	// at most 64 accounts, so at most 1024 slots are migrated.
+	const V1_SETS_PER_BLOCK: u32 = 16;
+
+	// migrate storage from v0 to v1.
+	//
+	// this splits each `EnumSet` of the v0 layout, a plain `Vec<T::AccountId>` of at most 64
+	// accounts, into one `EnumSlot` entry per account and sets `EnumSetLen`. It also builds the
+	// `Accounts` reverse lookup with its `HeldIndices` and initializes `LiveIndexCount`.
+	//
+	// every account keeps its index. If `T::EnumSetSize` is not the size of the v0 layout, the
+	// index is split into another set and position, and `NextEnumSet` is converted when the
+	// migration starts.
+	//
+	// there is no bound on the number of sets, so at most `V1_SETS_PER_BLOCK` of them are
+	// migrated per block, from `NextEnumSet` down to set 0, continuing from `MigrationCursor`.
+	// The set being filled is migrated first, so new accounts are given its next slot as
+	// before. The version is only bumped once set 0 was migrated.
+	pub fn to_v1<T: Trait>(version: &mut VersionNumber) {
+		if *version != 0 { return }
+
+		let mut set_index = match <Module<T> as Store>::MigrationCursor::get() {
+			Some(set_index) => set_index,
+			None => start_v1::<T>(),
+		};
+		let next_set_index = <Module<T>>::next_enum_set();
+		for _ in 0..V1_SETS_PER_BLOCK {
+			let set = <Module<T> as Store>::EnumSet::take(set_index);
+			if !set.is_empty() {
+				<Module<T> as Store>::LiveIndexCount::mutate(|c| *c += (set.len() as u32).into());
+			}
+			for (i, who) in (0..).zip(set) {
+				let index = v0_index::<T>(set_index, i).unwrap_or_else(Bounded::max_value);
+				let (new_set_index, new_i) = <Module<T>>::index_location(index);
+				<Module<T> as Store>::EnumSlot::insert(new_set_index, new_i, &who);
+				// only the set being filled is not full, its length is set by `start_v1`.
+				if new_i == 0 && new_set_index < next_set_index {
+					<Module<T> as Store>::EnumSetLen::insert(new_set_index, T::EnumSetSize::get());
+				}
+				// sets are migrated downwards, so each account ends up with the lowest index it
+				// holds.
+				<Module<T> as Store>::HeldIndices::insert(&who, index, index);
+				<Module<T> as Store>::Accounts::insert(&who, index);
+			}
+
+			set_index = match set_index.checked_sub(&One::one()) {
+				Some(next) => next,
+				None => {
+					<Module<T> as Store>::MigrationCursor::kill();
+					*version = CURRENT_VERSION;
+					frame_support::print("Finished migrating Indices storage to v1.");
+					return
+				}
+			};
+		}
+		<Module<T> as Store>::MigrationCursor::put(set_index);
+	}
+
+	// start the migration at the set being filled, which is returned.
+	//
+	// all sets of the v0 layout before it are full. `NextEnumSet` and the length of the set it
+	// points to are converted to `T::EnumSetSize` here, before any slot is migrated.
+	fn start_v1<T: Trait>() -> T::AccountIndex {
+		let set_index = <Module<T>>::next_enum_set();
+		let set_len = <Module<T> as Store>::EnumSet::decode_len(set_index).unwrap_or(0);
+		let end = v0_index::<T>(set_index, set_len as u32).unwrap_or_else(Bounded::max_value);
+		let (next_set_index, next_set_len) = <Module<T>>::index_location(end);
+
+		<Module<T> as Store>::NextEnumSet::put(next_set_index);
+		if next_set_len > 0 {
+			<Module<T> as Store>::EnumSetLen::insert(next_set_index, next_set_len);
+		}
+		set_index
+	}
+
+	// the index of the slot at `item_index` within the enum set `set_index` of the v0 layout.
+	fn v0_index<T: Trait>(set_index: T::AccountIndex, item_index: u32) -> Option<T::AccountIndex> {
+		set_index.checked_mul(&V0_ENUM_SET_SIZE.into())?.checked_add(&item_index.into())
+	}
+
+	pub(super) fn lookup_v0_index<T: Trait>(
+		index: T::AccountIndex,
+	) -> Result<T::AccountId, LookupIndexError> {
+		let enum_set_size = T::AccountIndex::from(V0_ENUM_SET_SIZE);
+		let set_index = index / enum_set_size;
+		// once the migration started, the set being filled is the first one it migrated.
+		let last_set_index = <Module<T> as Store>::MigrationCursor::get()
+			.unwrap_or_else(<Module<T>>::next_enum_set);
+		if set_index > last_set_index {
+			return Err(LookupIndexError::OutOfRange)
+		}
+		let i = TryInto::<usize>::try_into(index % enum_set_size)
+			.map_err(|_| LookupIndexError::OutOfRange)?;
+		<Module<T> as Store>::EnumSet::get(set_index).get(i).cloned()
+			.ok_or(LookupIndexError::Unassigned)
+	}
+
+	pub(super) fn perform_migrations<T: Trait>() {
+		<Module<T> as Store>::StorageVersion::mutate(|version| {
+			if *version == CURRENT_VERSION { return }
+
+			to_v1::<T>(version);
+		});
+	}
+
+	pub(super) fn is_migrated<T: Trait>(set_index: T::AccountIndex) -> bool {
+		// the migrated indices are those above a bound, so the set is migrated once its first
+		// index is.
+		is_index_migrated::<T>(set_index.saturating_mul(T::EnumSetSize::get().into()))
+	}
+
+	pub(super) fn is_index_migrated<T: Trait>(index: T::AccountIndex) -> bool {
+		<Module<T> as Store>::StorageVersion::get() == CURRENT_VERSION
+			|| <Module<T> as Store>::MigrationCursor::get()
+				.map_or(false, |next| index / V0_ENUM_SET_SIZE.into() > next)
+	}
+}
+
+#[cfg(not(any(test, feature = "migrate")))]
+mod inner {
+	pub(super) fn perform_migrations<T>() { }
+
+	pub(super) fn is_migrated<T: crate::Trait>(_set_index: T::AccountIndex) -> bool { true }
+
+	pub(super) fn is_index_migrated<T: crate::Trait>(_index: T::AccountIndex) -> bool { true }
+
+	pub(super) fn lookup_v0_index<T: crate::Trait>(
+		_index: T::AccountIndex,
+	) -> Result<T::AccountId, crate::LookupIndexError> {
+		Err(crate::LookupIndexError::Unassigned)
+	}
+}
+
+/// Perform all necessary storage migrations to get storage into the expected state for current
+/// logic. No-op if fully upgraded.
+pub(crate) fn perform_migrations<T: crate::Trait>() {
+	inner::perform_migrations::<T>();
+}
+
+/// Whether the enum set `set_index` is stored in the current layout.
+///
+/// The migration from v0 is spread over several blocks. Until it reaches all indices of a set,
+/// the set's slots must not be written.
+pub(crate) fn is_migrated<T: crate::Trait>(set_index: T::AccountIndex) -> bool {
+	inner::is_migrated::<T>(set_index)
+}
+
+/// Whether `index` is stored in the current layout.
+///
+/// Until the migration from v0 reaches it, its slot reads as empty, but it can be looked up with
+/// `lookup_v0_index`. A set can be partly migrated if `T::EnumSetSize` does not match the v0
+/// layout.
+pub(crate) fn is_index_migrated<T: crate::Trait>(index: T::AccountIndex) -> bool {
+	inner::is_index_migrated::<T>(index)
+}
+
+/// Lookup `index` in the storage of the v0 layout, which is only meaningful if `index` is not
+/// migrated yet.
+pub(crate) fn lookup_v0_index<T: crate::Trait>(
+	index: T::AccountIndex,
+) -> Result<T::AccountId, crate::LookupIndexError> {
+	inner::lookup_v0_index::<T>(index)
+}
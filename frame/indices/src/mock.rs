@@ -22,15 +22,38 @@ use std::{cell::RefCell, collections::HashSet};
 use sp_runtime::testing::Header;
 use sp_runtime::Perbill;
 use sp_core::H256;
-use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
-use crate::{GenesisConfig, Module, Trait, IsDeadAccount, OnNewAccount, ResolveHint};
+use frame_support::{
+	impl_outer_origin, impl_outer_event, parameter_types, ord_parameter_types, weights::Weight,
+	traits::Get,
+};
+use frame_system::EnsureSignedBy;
+use crate::{
+	GenesisConfig, Module, Trait, IsDeadAccount, OnNewAccount, ResolveHint, ReclaimGuard,
+	DepositPolicy, ReclaimPolicy,
+};
 
 impl_outer_origin!{
 	pub enum Origin for Runtime where system = frame_system {}
 }
 
+mod indices {
+	pub use crate::Event;
+}
+
+impl_outer_event!{
+	pub enum MetaEvent for Runtime where system = frame_system {
+		indices<T>,
+		pallet_balances<T>,
+	}
+}
+
 thread_local! {
 	static ALIVE: RefCell<HashSet<u64>> = Default::default();
+	static DEAD_DEPOSIT_POLICY: RefCell<DepositPolicy> = RefCell::new(DepositPolicy::Slash);
+	static RECLAIM_POLICY: RefCell<ReclaimPolicy> = RefCell::new(ReclaimPolicy::PreferHint);
+	static ENUM_SET_SIZE: RefCell<u32> = RefCell::new(64);
+	static GUARDED: RefCell<HashSet<u64>> = Default::default();
+	static LOGS: RefCell<Option<Vec<String>>> = RefCell::new(None);
 }
 
 pub fn make_account(who: u64) {
@@ -42,6 +65,33 @@ pub fn kill_account(who: u64) {
 	ALIVE.with(|a| a.borrow_mut().remove(&who));
 }
 
+/// Collects the messages logged for the `indices` target while `capture_logs` runs.
+struct CaptureLogger;
+impl log::Log for CaptureLogger {
+	fn enabled(&self, metadata: &log::Metadata) -> bool {
+		metadata.target() == "indices"
+	}
+
+	fn log(&self, record: &log::Record) {
+		if self.enabled(record.metadata()) {
+			LOGS.with(|l| l.borrow_mut().as_mut().map(|logs| logs.push(record.args().to_string())));
+		}
+	}
+
+	fn flush(&self) {}
+}
+
+static LOGGER: CaptureLogger = CaptureLogger;
+
+/// Run `f` and return the messages it logged for the `indices` target.
+pub fn capture_logs(f: impl FnOnce()) -> Vec<String> {
+	// only the first call installs the logger.
+	let _ = log::set_logger(&LOGGER).map(|()| log::set_max_level(log::LevelFilter::Trace));
+	LOGS.with(|l| *l.borrow_mut() = Some(Vec::new()));
+	f();
+	LOGS.with(|l| l.borrow_mut().take()).unwrap_or_default()
+}
+
 pub struct TestIsDeadAccount {}
 impl IsDeadAccount<u64> for TestIsDeadAccount {
 	fn is_dead_account(who: &u64) -> bool {
@@ -49,6 +99,51 @@ impl IsDeadAccount<u64> for TestIsDeadAccount {
 	}
 }
 
+pub struct TestDeadDepositPolicy;
+impl Get<DepositPolicy> for TestDeadDepositPolicy {
+	fn get() -> DepositPolicy {
+		DEAD_DEPOSIT_POLICY.with(|p| *p.borrow())
+	}
+}
+
+pub fn set_dead_deposit_policy(policy: DepositPolicy) {
+	DEAD_DEPOSIT_POLICY.with(|p| *p.borrow_mut() = policy);
+}
+
+pub struct TestReclaimPolicy;
+impl Get<ReclaimPolicy> for TestReclaimPolicy {
+	fn get() -> ReclaimPolicy {
+		RECLAIM_POLICY.with(|p| *p.borrow())
+	}
+}
+
+pub fn set_reclaim_policy(policy: ReclaimPolicy) {
+	RECLAIM_POLICY.with(|p| *p.borrow_mut() = policy);
+}
+
+pub struct TestEnumSetSize;
+impl Get<u32> for TestEnumSetSize {
+	fn get() -> u32 {
+		ENUM_SET_SIZE.with(|s| *s.borrow())
+	}
+}
+
+pub fn set_enum_set_size(size: u32) {
+	ENUM_SET_SIZE.with(|s| *s.borrow_mut() = size);
+}
+
+/// Keep the indices of `who` from being reclaimed.
+pub fn guard_account(who: u64) {
+	GUARDED.with(|g| g.borrow_mut().insert(who));
+}
+
+pub struct TestReclaimGuard;
+impl ReclaimGuard<u64, u64> for TestReclaimGuard {
+	fn can_reclaim(who: &u64, _: u64) -> bool {
+		!GUARDED.with(|g| g.borrow().contains(who))
+	}
+}
+
 pub struct TestResolveHint;
 impl ResolveHint<u64, u64> for TestResolveHint {
 	fn resolve_hint(who: &u64) -> Option<u64> {
@@ -68,6 +163,16 @@ parameter_types! {
 	pub const MaximumBlockWeight: Weight = 1024;
 	pub const MaximumBlockLength: u32 = 2 * 1024;
 	pub const AvailableBlockRatio: Perbill = Perbill::one();
+	pub const MaxReclaimSteps: u32 = 8;
+	pub const IndexSpaceWarningThreshold: u64 = 8;
+	pub const IndexDeposit: u64 = 10;
+	pub const ExistentialDeposit: u64 = 0;
+	pub const TransferFee: u64 = 0;
+	pub const CreationFee: u64 = 0;
+}
+
+ord_parameter_types! {
+	pub const One: u64 = 1;
 }
 
 impl frame_system::Trait for Runtime {
@@ -80,7 +185,7 @@ impl frame_system::Trait for Runtime {
 	type AccountId = u64;
 	type Lookup = Indices;
 	type Header = Header;
-	type Event = ();
+	type Event = MetaEvent;
 	type BlockHashCount = BlockHashCount;
 	type MaximumBlockWeight = MaximumBlockWeight;
 	type MaximumBlockLength = MaximumBlockLength;
@@ -89,27 +194,86 @@ impl frame_system::Trait for Runtime {
 	type ModuleToIndex = ();
 }
 
+impl pallet_balances::Trait for Runtime {
+	type Balance = u64;
+	type OnFreeBalanceZero = ();
+	type OnReapAccount = System;
+	type OnNewAccount = ();
+	type Event = MetaEvent;
+	type TransferPayment = ();
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type TransferFee = TransferFee;
+	type CreationFee = CreationFee;
+}
+
 impl Trait for Runtime {
 	type AccountIndex = u64;
 	type IsDeadAccount = TestIsDeadAccount;
 	type ResolveHint = TestResolveHint;
-	type Event = ();
+	type ReclaimGuard = TestReclaimGuard;
+	type EnumSetSize = TestEnumSetSize;
+	type MaxReclaimSteps = MaxReclaimSteps;
+	type ReclaimPolicy = TestReclaimPolicy;
+	type IndexSpaceWarningThreshold = IndexSpaceWarningThreshold;
+	type ForceOrigin = EnsureSignedBy<One, u64>;
+	type Currency = Balances;
+	type IndexDeposit = IndexDeposit;
+	type DeadDepositPolicy = TestDeadDepositPolicy;
+	type Slashed = ();
+	type Event = MetaEvent;
 }
 
 pub fn new_test_ext() -> sp_io::TestExternalities {
+	new_test_ext_with_reserved(0)
+}
+
+pub fn new_test_ext_with_reserved(reserved: u64) -> sp_io::TestExternalities {
+	new_test_ext_with(vec![1, 2, 3, 4], reserved, vec![])
+}
+
+/// Genesis with the given live accounts at the indices from 0 on.
+pub fn new_test_ext_with_ids(ids: Vec<u64>) -> sp_io::TestExternalities {
+	new_test_ext_with(ids, 0, vec![])
+}
+
+/// Genesis with the live accounts 1 to 4 and the given accounts at the given indices, which are
+/// dead as they were never made.
+pub fn new_test_ext_with_dead_ids(dead_ids: Vec<(u64, u64)>) -> sp_io::TestExternalities {
+	new_test_ext_with(vec![1, 2, 3, 4], 0, dead_ids)
+}
+
+fn new_test_ext_with(
+	ids: Vec<u64>,
+	reserved: u64,
+	dead_ids: Vec<(u64, u64)>,
+) -> sp_io::TestExternalities {
 	{
 		ALIVE.with(|a| {
 			let mut h = a.borrow_mut();
 			h.clear();
-			for i in 1..5 { h.insert(i); }
+			h.extend(ids.iter().cloned());
 		});
 	}
+	set_dead_deposit_policy(DepositPolicy::Slash);
+	set_reclaim_policy(ReclaimPolicy::PreferHint);
+	set_enum_set_size(64);
+	GUARDED.with(|g| g.borrow_mut().clear());
 
 	let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+	// accounts from 100 on have no funds.
+	pallet_balances::GenesisConfig::<Runtime> {
+		balances: (1..100).chain(257..260).map(|who| (who, 100)).collect(),
+		vesting: vec![],
+	}.assimilate_storage(&mut t).unwrap();
 	GenesisConfig::<Runtime> {
-		ids: vec![1, 2, 3, 4]
+		ids,
+		reserved,
+		dead_ids,
 	}.assimilate_storage(&mut t).unwrap();
 	t.into()
 }
 
+pub type System = frame_system::Module<Runtime>;
+pub type Balances = pallet_balances::Module<Runtime>;
 pub type Indices = Module<Runtime>;
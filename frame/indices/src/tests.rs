@@ -19,7 +19,21 @@
 #![cfg(test)]
 
 use super::*;
-use crate::mock::{Indices, new_test_ext, make_account, kill_account, TestIsDeadAccount};
+use crate::mock::{
+	Indices, System, Balances, Origin, Runtime, MetaEvent, new_test_ext, new_test_ext_with_reserved,
+	new_test_ext_with_dead_ids, new_test_ext_with_ids, make_account, kill_account,
+	set_dead_deposit_policy, set_reclaim_policy, set_enum_set_size, guard_account, capture_logs,
+	TestIsDeadAccount,
+};
+use frame_support::{
+	assert_ok, assert_noop, StorageMap, StorageDoubleMap, StorageValue, storage::unhashed,
+	weights::GetDispatchInfo,
+};
+use sp_runtime::traits::{OnInitialize, BadOrigin};
+
+fn events() -> Vec<MetaEvent> {
+	System::events().into_iter().map(|r| r.event).collect()
+}
 
 #[test]
 fn indexing_lookup_should_work() {
@@ -65,3 +79,1546 @@ fn alive_account_should_prevent_reclaim() {
 		assert_eq!(Indices::lookup_index(4), Some(257));
 	});
 }
+
+#[test]
+fn reclaim_guard_should_prevent_reclaim() {
+	new_test_ext().execute_with(|| {
+		kill_account(2);
+		kill_account(3);
+		guard_account(2);
+		assert!(!Indices::can_reclaim(1));
+		assert!(Indices::can_reclaim(2));
+		assert_noop!(Indices::claim(Origin::signed(5), 1), Error::<Runtime>::InUse);
+
+		// neither the hint nor the scan for dead indices hands out index 1.
+		make_account(1 + 256);
+		assert_eq!(Indices::index_of(1 + 256), Some(2));
+		make_account(6);
+		assert_eq!(Indices::index_of(6), Some(4));
+		assert_eq!(Indices::lookup_index(1), Some(2));
+	});
+}
+
+#[test]
+fn ensure_indexed_should_not_assign_twice() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Indices::ensure_indexed(&3), 2);
+
+		let index = Indices::ensure_indexed(&5);
+		assert_eq!(index, 4);
+		assert_eq!(Indices::ensure_indexed(&5), index);
+		assert_eq!(Indices::enum_set_len(0), 5);
+		assert_eq!(Indices::live_index_count(), 5);
+	});
+}
+
+#[test]
+fn new_accounts_should_log_how_they_are_indexed() {
+	new_test_ext().execute_with(|| {
+		kill_account(2);
+		assert_eq!(
+			capture_logs(|| make_account(1 + 256)),
+			vec!["New account 257 reclaims its hinted index 1 from Some(2)"],
+		);
+
+		// the hinted index 3 is held by the live account 4.
+		assert_eq!(capture_logs(|| make_account(3 + 256)), vec![
+			"Hinted index 3 of new account 259 is not reclaimable",
+			"New account 259 is given the new index 4",
+		]);
+	});
+}
+
+#[test]
+fn claim_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::claim(Origin::signed(5), 4));
+		assert_eq!(Indices::lookup_index(4), Some(5));
+
+		kill_account(2);
+		assert_ok!(Indices::claim(Origin::signed(6), 1));
+		assert_eq!(Indices::lookup_index(1), Some(6));
+	});
+}
+
+#[test]
+fn claim_should_reserve_the_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::claim(Origin::signed(1), 4));
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert_eq!(Balances::free_balance(1), 90);
+		assert_eq!(Indices::deposit_of(4), 10);
+
+		// indices assigned at genesis or to new accounts hold no deposit.
+		make_account(6);
+		assert_eq!(Indices::lookup_index(5), Some(6));
+		assert_eq!(Indices::deposit_of(5), 0);
+		assert_eq!(Indices::deposit_of(0), 0);
+		assert_eq!(Balances::reserved_balance(6), 0);
+	});
+}
+
+#[test]
+fn claim_should_fail_without_free_balance() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Indices::claim(Origin::signed(100), 4),
+			pallet_balances::Error::<Runtime, _>::InsufficientBalance,
+		);
+		assert_eq!(Indices::lookup_index(4), None);
+		assert_eq!(Indices::deposit_of(4), 0);
+	});
+}
+
+#[test]
+fn free_should_return_the_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::claim(Origin::signed(1), 4));
+		assert_ok!(Indices::free(Origin::signed(1), 4));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 100);
+		assert_eq!(Indices::deposit_of(4), 0);
+
+		// so does taking the index by force.
+		assert_ok!(Indices::claim(Origin::signed(1), 4));
+		assert_ok!(Indices::force_reclaim(Origin::ROOT, 4));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 100);
+	});
+}
+
+#[test]
+fn transfer_should_move_the_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::claim(Origin::signed(1), 4));
+		assert_ok!(Indices::transfer(Origin::signed(1), 2, 4));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(2), 10);
+		assert_eq!(Indices::deposit_of(4), 10);
+
+		assert_noop!(
+			Indices::transfer(Origin::signed(2), 100, 4),
+			pallet_balances::Error::<Runtime, _>::InsufficientBalance,
+		);
+		assert_eq!(Indices::lookup_index(4), Some(2));
+	});
+}
+
+#[test]
+fn reclaiming_from_a_dead_account_should_apply_the_deposit_policy() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::claim(Origin::signed(1), 4));
+		kill_account(1);
+		make_account(4 + 256);
+		assert_eq!(Indices::lookup_index(4), Some(4 + 256));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 90);
+		assert_eq!(Indices::deposit_of(4), 0);
+	});
+
+	new_test_ext().execute_with(|| {
+		set_dead_deposit_policy(DepositPolicy::Return);
+		assert_ok!(Indices::claim(Origin::signed(1), 4));
+		kill_account(1);
+		assert_ok!(Indices::claim(Origin::signed(6), 4));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 100);
+		assert_eq!(Indices::deposit_of(4), 10);
+	});
+}
+
+#[test]
+fn claim_should_fail_for_live_or_non_contiguous_index() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Indices::claim(Origin::signed(5), 1), Error::<Runtime>::InUse);
+		assert_noop!(Indices::claim(Origin::signed(5), 5), Error::<Runtime>::NotContiguous);
+	});
+}
+
+#[test]
+fn claim_should_fail_beyond_the_next_enum_set() {
+	new_test_ext().execute_with(|| {
+		// the first slot of an empty set far beyond the one being filled.
+		assert_noop!(Indices::claim(Origin::signed(5), 64 * 10), Error::<Runtime>::NotContiguous);
+		assert_noop!(Indices::claim(Origin::signed(5), 64), Error::<Runtime>::NotContiguous);
+
+		assert_ok!(Indices::claim(Origin::signed(5), 4));
+		assert_eq!(Indices::lookup_index(4), Some(5));
+	});
+}
+
+#[test]
+fn free_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Indices::free(Origin::signed(1), 1), Error::<Runtime>::NotOwner);
+
+		assert_ok!(Indices::free(Origin::signed(2), 1));
+		assert!(!TestIsDeadAccount::is_dead_account(&2));
+		assert_eq!(Indices::lookup_index(1), None);
+		assert!(Indices::can_reclaim(1));
+
+		make_account(1 + 256);				// id 257 takes freed index 1.
+		assert_eq!(Indices::lookup_index(1), Some(257));
+		assert!(!Indices::can_reclaim(1));
+	});
+}
+
+#[test]
+fn freed_index_can_be_claimed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::free(Origin::signed(3), 2));
+		assert_ok!(Indices::claim(Origin::signed(5), 2));
+		assert_eq!(Indices::lookup_index(2), Some(5));
+		assert_eq!(Indices::enum_set(0), vec![Some(1), Some(2), Some(5), Some(4)]);
+	});
+}
+
+#[test]
+fn freeing_a_middle_index_should_not_renumber_neighbours() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::free(Origin::signed(2), 1));
+		assert_eq!(Indices::enum_set(0), vec![Some(1), None, Some(3), Some(4)]);
+		assert_eq!(Indices::lookup_index(0), Some(1));
+		assert_eq!(Indices::lookup_index(1), None);
+		assert_eq!(Indices::lookup_index(2), Some(3));
+		assert_eq!(Indices::lookup_index(3), Some(4));
+
+		// the hole is filled by the next new account.
+		make_account(5);
+		assert_eq!(Indices::lookup_index(1), Some(5));
+		assert_eq!(Indices::lookup_index(2), Some(3));
+		assert_eq!(Indices::lookup_index(4), None);
+	});
+}
+
+#[test]
+fn transfer_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::transfer(Origin::signed(2), 3, 1));
+		assert_eq!(Indices::lookup_index(1), Some(3));
+		assert_eq!(Indices::lookup_index(2), Some(3));
+
+		assert_noop!(Indices::transfer(Origin::signed(2), 5, 1), Error::<Runtime>::NotOwner);
+		assert_noop!(Indices::transfer(Origin::signed(3), 3, 1), Error::<Runtime>::NotTransfer);
+		assert_noop!(Indices::transfer(Origin::signed(3), 5, 4), Error::<Runtime>::NotOwner);
+	});
+}
+
+#[test]
+fn reverse_lookup_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Indices::index_of(&1), Some(0));
+		assert_eq!(Indices::index_of(&4), Some(3));
+
+		make_account(5);
+		assert_eq!(Indices::index_of(&5), Some(4));
+
+		kill_account(2);
+		make_account(1 + 256);
+		assert_eq!(Indices::index_of(&2), None);
+		assert_eq!(Indices::index_of(&257), Some(1));
+
+		assert_ok!(Indices::transfer(Origin::signed(3), 6, 2));
+		assert_eq!(Indices::index_of(&3), None);
+		assert_eq!(Indices::index_of(&6), Some(2));
+
+		assert_ok!(Indices::free(Origin::signed(6), 2));
+		assert_eq!(Indices::index_of(&6), None);
+
+		assert_ok!(Indices::claim(Origin::signed(7), 2));
+		assert_eq!(Indices::index_of(&7), Some(2));
+	});
+}
+
+#[test]
+fn reverse_lookup_should_move_to_another_held_index() {
+	new_test_ext().execute_with(|| {
+		// account 3 holds indices 1 and 2.
+		assert_ok!(Indices::transfer(Origin::signed(2), 3, 1));
+		assert_eq!(Indices::index_of(&3), Some(2));
+
+		assert_ok!(Indices::free(Origin::signed(3), 2));
+		assert_eq!(Indices::index_of(&3), Some(1));
+		assert_eq!(Indices::unlookup(3), address::Address::Index(1));
+
+		// giving up an index that is not the reverse lookup keeps it.
+		assert_ok!(Indices::claim(Origin::signed(3), 2));
+		assert_ok!(Indices::transfer(Origin::signed(3), 5, 2));
+		assert_eq!(Indices::index_of(&3), Some(1));
+
+		assert_ok!(Indices::force_reclaim(Origin::ROOT, 1));
+		assert_eq!(Indices::index_of(&3), None);
+		assert_eq!(Indices::unlookup(3), address::Address::Id(3));
+	});
+}
+
+#[test]
+fn reclaim_should_deposit_event() {
+	new_test_ext().execute_with(|| {
+		make_account(5);
+		assert_eq!(events(), vec![MetaEvent::indices(RawEvent::NewAccountIndex(5, 4))]);
+
+		kill_account(2);
+		make_account(1 + 256);
+		assert_eq!(events(), vec![
+			MetaEvent::indices(RawEvent::NewAccountIndex(5, 4)),
+			MetaEvent::indices(RawEvent::IndexReclaimed(2, 257, 1, 1)),
+		]);
+	});
+}
+
+#[test]
+fn lookup_indices_should_match_lookup_index() {
+	new_test_ext().execute_with(|| {
+		for i in 5..70 {
+			make_account(i);
+		}
+		assert_ok!(Indices::free(Origin::signed(3), 2));
+
+		let indices = vec![65, 2, 0, 64, 3, 66, 200, 0];
+		let expected = indices.iter().map(|&i| Indices::lookup_index(i)).collect::<Vec<_>>();
+		assert_eq!(Indices::lookup_indices(&indices), expected);
+		assert_eq!(Indices::lookup_indices(&[0, 2, 65, 69]), vec![Some(1), None, Some(66), None]);
+	});
+}
+
+#[test]
+fn is_index_assigned_should_work() {
+	new_test_ext().execute_with(|| {
+		assert!(Indices::is_index_assigned(0));
+		assert!(Indices::is_index_assigned(3));
+		assert!(!Indices::is_index_assigned(4));
+		assert!(!Indices::is_index_assigned(64));
+
+		assert_ok!(Indices::free(Origin::signed(2), 1));
+		assert!(!Indices::is_index_assigned(1));
+	});
+}
+
+#[test]
+fn reserved_indices_should_not_be_handed_out() {
+	new_test_ext_with_reserved(70).execute_with(|| {
+		assert_eq!(Indices::next_enum_set(), 1);
+		assert_eq!(Indices::lookup_index(3), Some(4));
+		assert_eq!(Indices::lookup_index(4), None);
+		assert_eq!(Indices::lookup_index(69), None);
+		assert!(!Indices::can_reclaim(4));
+		assert!(!Indices::is_index_assigned(69));
+
+		make_account(5);
+		assert_eq!(Indices::lookup_index(70), Some(5));
+		assert_eq!(Indices::index_of(5), Some(70));
+
+		// reserved slots can neither be reclaimed by a hint nor claimed.
+		make_account(4 + 256);
+		assert_eq!(Indices::lookup_index(71), Some(4 + 256));
+		assert_eq!(Indices::lookup_index(4), None);
+		assert_noop!(Indices::claim(Origin::signed(6), 4), Error::<Runtime>::Reserved);
+		assert_noop!(Indices::claim(Origin::signed(6), 64), Error::<Runtime>::Reserved);
+	});
+}
+
+#[test]
+fn reserved_indices_of_genesis_ids_should_not_be_reclaimed() {
+	new_test_ext_with_reserved(6).execute_with(|| {
+		assert_eq!(Indices::reserved_up_to(), 6);
+		assert_ok!(Indices::free(Origin::signed(1), 0));
+		kill_account(2);
+		assert!(!Indices::can_reclaim(0));
+		assert!(!Indices::can_reclaim(1));
+		assert_eq!(Indices::can_reclaim_batch(&[0, 1, 6]), vec![false, false, false]);
+
+		// neither the free list, nor a hint, nor the scan hands them out.
+		make_account(5);
+		assert_eq!(Indices::index_of(5), Some(6));
+		make_account(256);
+		assert_eq!(Indices::index_of(256), Some(7));
+		make_account(1 + 256);
+		assert_eq!(Indices::index_of(1 + 256), Some(8));
+		assert_eq!(Indices::lookup_index(0), None);
+		assert_eq!(Indices::lookup_index(1), Some(2));
+	});
+}
+
+#[test]
+fn claim_should_stop_at_reserved_indices() {
+	new_test_ext_with_reserved(6).execute_with(|| {
+		assert!(Indices::is_reserved(4));
+		assert!(Indices::is_reserved(5));
+		assert!(!Indices::is_reserved(6));
+
+		assert_noop!(Indices::claim(Origin::signed(6), 5), Error::<Runtime>::Reserved);
+		assert_ok!(Indices::claim(Origin::signed(6), 6));
+		assert_eq!(Indices::lookup_index(6), Some(6));
+
+		// the force origin may still assign a reserved index, which stays reserved.
+		assert_ok!(Indices::force_set_index(Origin::ROOT, 7, 5));
+		assert_eq!(Indices::lookup_index(5), Some(7));
+		assert!(Indices::is_reserved(5));
+		assert!(Indices::is_reserved(4));
+
+		// once given up, nobody can take it.
+		assert_ok!(Indices::free(Origin::signed(7), 5));
+		assert_noop!(Indices::claim(Origin::signed(8), 5), Error::<Runtime>::Reserved);
+		assert!(!Indices::can_reclaim(5));
+		make_account(5 + 256);
+		assert_ne!(Indices::index_of(5 + 256), Some(5));
+		assert_eq!(Indices::lookup_index(5), None);
+	});
+}
+
+#[test]
+fn enumerate_should_work() {
+	new_test_ext().execute_with(|| {
+		for i in 5..70 {
+			make_account(i);
+		}
+		kill_account(3);
+		assert_ok!(Indices::free(Origin::signed(4), 3));
+
+		let expected = (0..69).filter(|&i| i != 3).map(|i| (i, i + 1)).collect::<Vec<_>>();
+		assert_eq!(Indices::enumerate().collect::<Vec<_>>(), expected);
+	});
+}
+
+#[test]
+fn lookup_address_ref_should_match_lookup_address() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::free(Origin::signed(3), 2));
+
+		let addresses = vec![
+			address::Address::Id(7),
+			address::Address::Index(1),
+			address::Address::Index(2),
+			address::Address::Index(4),
+		];
+		for a in addresses {
+			assert_eq!(Indices::lookup_address_ref(&a), Indices::lookup_address(a));
+		}
+	});
+}
+
+#[test]
+fn live_index_count_should_work() {
+	new_test_ext_with_reserved(2).execute_with(|| {
+		assert_eq!(Indices::live_index_count(), 4);
+
+		// new allocation.
+		make_account(5);
+		assert_eq!(Indices::live_index_count(), 5);
+		assert_ok!(Indices::claim(Origin::signed(6), 5));
+		assert_eq!(Indices::live_index_count(), 6);
+
+		// reclaim of a dead account.
+		kill_account(3);
+		make_account(2 + 256);
+		assert_eq!(Indices::live_index_count(), 6);
+		kill_account(4);
+		assert_ok!(Indices::claim(Origin::signed(8), 3));
+		assert_eq!(Indices::live_index_count(), 6);
+
+		// free and refill the slot.
+		assert_ok!(Indices::free(Origin::signed(8), 3));
+		assert_eq!(Indices::live_index_count(), 5);
+		make_account(3 + 256);
+		assert_eq!(Indices::lookup_index(3), Some(3 + 256));
+		assert_eq!(Indices::live_index_count(), 6);
+		assert_ok!(Indices::free(Origin::signed(2 + 256), 2));
+		assert_ok!(Indices::claim(Origin::signed(9), 2));
+		assert_eq!(Indices::live_index_count(), 6);
+
+		assert_eq!(Indices::live_index_count(), Indices::enumerate().count() as u64);
+	});
+}
+
+#[test]
+fn try_lookup_index_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::free(Origin::signed(2), 1));
+
+		assert_eq!(Indices::try_lookup_index(0), Ok(1));
+		assert_eq!(Indices::try_lookup_index(1), Err(LookupIndexError::Unassigned));
+		assert_eq!(Indices::try_lookup_index(4), Err(LookupIndexError::Unassigned));
+		assert_eq!(Indices::try_lookup_index(63), Err(LookupIndexError::Unassigned));
+		assert_eq!(Indices::try_lookup_index(64), Err(LookupIndexError::OutOfRange));
+
+		for i in 5..70 {
+			make_account(i);
+		}
+		// account 5 took the freed index.
+		assert_eq!(Indices::try_lookup_index(64), Ok(66));
+		assert_eq!(Indices::try_lookup_index(127), Err(LookupIndexError::Unassigned));
+		assert_eq!(Indices::try_lookup_index(128), Err(LookupIndexError::OutOfRange));
+	});
+}
+
+#[test]
+fn lookups_should_stop_at_the_next_enum_set() {
+	new_test_ext().execute_with(|| {
+		// set 0 is the one being filled.
+		assert_eq!(Indices::next_enum_set(), 0);
+		assert_eq!(Indices::lookup_index(3), Some(4));
+		assert_eq!(Indices::lookup_index(64), None);
+		assert!(!Indices::can_reclaim(64));
+
+		// forcing the last index of the set being filled moves the bound to the next set.
+		assert_ok!(Indices::force_set_index(Origin::ROOT, 9, 63));
+		assert_eq!(Indices::next_enum_set(), 1);
+		assert_eq!(Indices::lookup_index(3), Some(4));
+		assert_eq!(Indices::lookup_index(63), Some(9));
+		assert!(Indices::can_reclaim(63));
+		assert_eq!(Indices::try_lookup_index(62), Err(LookupIndexError::Unassigned));
+		assert_eq!(Indices::try_lookup_index(127), Err(LookupIndexError::Unassigned));
+		assert_eq!(Indices::try_lookup_index(128), Err(LookupIndexError::OutOfRange));
+		assert!(!Indices::can_reclaim(128));
+
+		// the set being filled is extended without moving the bound.
+		assert_ok!(Indices::force_set_index(Origin::ROOT, 10, 70));
+		assert_eq!(Indices::next_enum_set(), 1);
+		assert_eq!(Indices::lookup_index(70), Some(10));
+		assert_eq!(Indices::lookup_index(63), Some(9));
+	});
+}
+
+#[test]
+fn set_enum_set_for_test_should_place_accounts() {
+	new_test_ext().execute_with(|| {
+		Indices::set_enum_set_for_test(0, (100..164).collect());
+		assert_eq!(Indices::lookup_index(3), Some(103));
+		assert_eq!(Indices::index_of(1), None);
+		assert_eq!(Indices::next_enum_set(), 1);
+		assert_eq!(Indices::live_index_count(), 64);
+
+		Indices::set_enum_set_for_test(1, vec![10, 11, 12]);
+		assert_eq!(Indices::lookup_index(64), Some(10));
+		assert_eq!(Indices::lookup_index(66), Some(12));
+		assert_eq!(Indices::lookup_index(67), None);
+		assert_eq!(Indices::index_of(11), Some(65));
+		assert_eq!(Indices::lookup_index(3), Some(103));
+		assert_eq!(Indices::next_enum_set(), 1);
+		assert_eq!(Indices::live_index_count(), 67);
+
+		// the previous content is replaced.
+		Indices::set_enum_set_for_test(1, vec![13]);
+		assert_eq!(Indices::enum_set(1), vec![Some(13)]);
+		assert_eq!(Indices::index_of(10), None);
+		assert_eq!(Indices::live_index_count(), 65);
+	});
+}
+
+#[test]
+#[should_panic(expected = "The enum set is beyond the one being filled")]
+fn set_enum_set_for_test_should_not_skip_sets() {
+	new_test_ext().execute_with(|| {
+		Indices::set_enum_set_for_test(2, vec![10, 11, 12]);
+	});
+}
+
+#[test]
+fn force_set_index_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Indices::force_set_index(Origin::signed(2), 5, 1), BadOrigin);
+
+		// overwrite a live account.
+		assert_ok!(Indices::force_set_index(Origin::ROOT, 5, 1));
+		assert_eq!(Indices::lookup_index(1), Some(5));
+		assert_eq!(Indices::index_of(2), None);
+		assert_eq!(Indices::index_of(5), Some(1));
+		assert_eq!(Indices::live_index_count(), 4);
+		assert_eq!(events(), vec![
+			MetaEvent::indices(RawEvent::IndexFreed(2, 1)),
+			MetaEvent::indices(RawEvent::NewAccountIndex(5, 1)),
+		]);
+
+		// beyond the allocated slots, by `T::ForceOrigin`.
+		assert_ok!(Indices::force_set_index(Origin::signed(1), 6, 10));
+		assert_eq!(Indices::next_enum_set(), 0);
+		assert_eq!(Indices::lookup_index(10), Some(6));
+		assert_eq!(Indices::try_lookup_index(9), Err(LookupIndexError::Unassigned));
+		assert_eq!(Indices::live_index_count(), 5);
+
+		// the forced accounts are dead, so their indices are reclaimed by new accounts.
+		make_account(7);
+		assert_eq!(Indices::lookup_index(1), Some(7));
+	});
+}
+
+#[test]
+fn force_set_index_should_fail_beyond_the_next_enum_set() {
+	new_test_ext().execute_with(|| {
+		// an index several sets ahead of the one being filled would abandon the sets in between.
+		assert_noop!(
+			Indices::force_set_index(Origin::ROOT, 9, 64 * 3 + 8),
+			Error::<Runtime>::NotContiguous,
+		);
+		assert_noop!(
+			Indices::force_set_index(Origin::ROOT, 9, 64),
+			Error::<Runtime>::NotContiguous,
+		);
+		assert_eq!(Indices::next_enum_set(), 0);
+		assert_eq!(Indices::enum_set_len(3), 0);
+
+		// once set 0 is full, set 1 is the one being filled.
+		for i in 5..65 {
+			make_account(i);
+		}
+		assert_eq!(Indices::next_enum_set(), 1);
+		assert_ok!(Indices::force_set_index(Origin::ROOT, 9, 64));
+		assert_eq!(Indices::lookup_index(64), Some(9));
+		assert_noop!(
+			Indices::force_set_index(Origin::ROOT, 10, 64 * 3),
+			Error::<Runtime>::NotContiguous,
+		);
+	});
+}
+
+#[test]
+fn reclaims_should_be_counted_per_index() {
+	new_test_ext().execute_with(|| {
+		// by the resolve hint.
+		kill_account(2);
+		make_account(1 + 256);
+		assert_eq!(Indices::lookup_index(1), Some(257));
+		assert_eq!(Indices::reclaim_count(1), 1);
+
+		// by the scan for reclaimable indices.
+		kill_account(1 + 256);
+		make_account(6);
+		assert_eq!(Indices::lookup_index(1), Some(6));
+		assert_eq!(Indices::reclaim_count(1), 2);
+		let event = RawEvent::IndexReclaimed(257, 6, 1, 2);
+		assert_eq!(events().last(), Some(&MetaEvent::indices(event)));
+
+		// taking an index by force counts as well.
+		assert_ok!(Indices::force_reclaim(Origin::ROOT, 1));
+		assert_eq!(Indices::reclaim_count(1), 3);
+
+		// a fresh index and one freed by its holder are not reclaimed.
+		make_account(7);
+		assert_eq!(Indices::lookup_index(1), Some(7));
+		make_account(8);
+		assert_eq!(Indices::lookup_index(4), Some(8));
+		assert_ok!(Indices::free(Origin::signed(3), 2));
+		assert_eq!(Indices::reclaim_count(1), 3);
+		assert_eq!(Indices::reclaim_count(2), 0);
+		assert_eq!(Indices::reclaim_count(4), 0);
+	});
+}
+
+#[test]
+fn force_reclaim_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Indices::force_reclaim(Origin::signed(2), 1), BadOrigin);
+		assert_noop!(Indices::force_reclaim(Origin::ROOT, 5), Error::<Runtime>::NotAssigned);
+
+		// account 2 is alive, but its index is taken away nonetheless.
+		assert!(!Indices::can_reclaim(1));
+		assert_ok!(Indices::force_reclaim(Origin::ROOT, 1));
+		assert!(!TestIsDeadAccount::is_dead_account(&2));
+		assert_eq!(Indices::lookup_index(1), None);
+		assert_eq!(Indices::index_of(2), None);
+		assert_eq!(Indices::live_index_count(), 3);
+		assert!(Indices::can_reclaim(1));
+		assert_eq!(events(), vec![MetaEvent::indices(RawEvent::IndexFreed(2, 1))]);
+
+		// by `T::ForceOrigin`, the freed index is handed to the next new account.
+		assert_ok!(Indices::force_reclaim(Origin::signed(1), 2));
+		assert_noop!(Indices::force_reclaim(Origin::signed(1), 2), Error::<Runtime>::NotAssigned);
+		make_account(7);
+		assert_eq!(Indices::lookup_index(2), Some(7));
+	});
+}
+
+#[test]
+fn clear_dead_indices_should_free_only_dead_slots() {
+	new_test_ext().execute_with(|| {
+		kill_account(2);
+		kill_account(3);
+		guard_account(3);
+		kill_account(4);
+
+		assert_ok!(Indices::clear_dead_indices(Origin::signed(1), 64));
+		assert_eq!(Indices::enum_set(0), vec![Some(1), None, Some(3), None]);
+		assert_eq!(Indices::free_indices(), vec![1, 3]);
+		assert_eq!(Indices::live_index_count(), 2);
+		assert_eq!(Indices::index_of(2), None);
+		assert_eq!(Indices::reclaim_count(1), 1);
+		assert_eq!(
+			events(),
+			vec![
+				MetaEvent::indices(RawEvent::IndexFreed(2, 1)),
+				MetaEvent::indices(RawEvent::IndexFreed(4, 3)),
+			],
+		);
+
+		// the most recently freed index is handed out first.
+		make_account(5);
+		assert_eq!(Indices::lookup_index(3), Some(5));
+	});
+}
+
+#[test]
+fn clear_dead_indices_should_respect_max() {
+	new_test_ext().execute_with(|| {
+		for who in 1..5 {
+			kill_account(who);
+		}
+
+		assert_ok!(Indices::clear_dead_indices(Origin::signed(1), 0));
+		assert_eq!(Indices::live_index_count(), 4);
+
+		assert_ok!(Indices::clear_dead_indices(Origin::signed(1), 2));
+		assert_eq!(Indices::enum_set(0), vec![None, None, Some(3), Some(4)]);
+		assert_eq!(Indices::clear_cursor(), 2);
+	});
+}
+
+#[test]
+fn clear_dead_indices_should_wrap_around() {
+	new_test_ext().execute_with(|| {
+		kill_account(1);
+
+		assert_ok!(Indices::clear_dead_indices(Origin::signed(1), 3));
+		assert_eq!(Indices::enum_set(0), vec![None, Some(2), Some(3), Some(4)]);
+		assert_eq!(Indices::clear_cursor(), 3);
+
+		// index 3 is inspected, then the scan wraps around to inspect 0 and 1.
+		kill_account(2);
+		kill_account(4);
+		assert_ok!(Indices::clear_dead_indices(Origin::signed(1), 3));
+		assert_eq!(Indices::enum_set(0), vec![None, None, Some(3), None]);
+		assert_eq!(Indices::clear_cursor(), 2);
+
+		// each slot is inspected at most once per call.
+		assert_ok!(Indices::clear_dead_indices(Origin::signed(1), 10));
+		assert_eq!(Indices::clear_cursor(), 2);
+	});
+}
+
+#[test]
+#[should_panic(expected = "Account 2 appears twice in the genesis ids.")]
+fn genesis_should_reject_duplicate_ids() {
+	let _ = GenesisConfig::<Runtime> {
+		ids: vec![1, 2, 3, 2],
+		reserved: 0,
+		dead_ids: vec![],
+	}.build_storage();
+}
+
+#[test]
+#[should_panic(expected = "Genesis index 1 is assigned twice.")]
+fn genesis_should_reject_dead_ids_at_assigned_indices() {
+	let _ = GenesisConfig::<Runtime> {
+		ids: vec![1, 2, 3],
+		reserved: 0,
+		dead_ids: vec![(5, 4), (1, 7)],
+	}.build_storage();
+}
+
+#[test]
+#[should_panic(expected = "18446744073709551615 is too far beyond the other indices.")]
+fn genesis_should_reject_dead_ids_far_beyond_the_other_indices() {
+	let _ = GenesisConfig::<Runtime> {
+		ids: vec![1, 2, 3],
+		reserved: 0,
+		dead_ids: vec![(5, 4), (u64::max_value(), 7)],
+	}.build_storage();
+}
+
+#[test]
+fn genesis_should_allow_dead_ids_within_an_enum_set_beyond_the_other_indices() {
+	new_test_ext_with_dead_ids(vec![(68, 10)]).execute_with(|| {
+		assert_eq!(Indices::lookup_index(68), Some(10));
+		assert_eq!(Indices::enum_set_len(1), 5);
+	});
+}
+
+#[test]
+fn genesis_should_start_a_new_set_after_a_full_last_set() {
+	new_test_ext_with_dead_ids(vec![(63, 10), (20, 11)]).execute_with(|| {
+		assert_eq!(Indices::next_enum_set(), 1);
+		assert_eq!(Indices::enum_set_len(0), 64);
+		assert_eq!(Indices::enum_set_len(1), 0);
+		assert_eq!(Indices::lookup_index(63), Some(10));
+
+		assert_ok!(Indices::claim(Origin::signed(5), 64));
+		assert_eq!(Indices::enum_set_len(1), 1);
+	});
+}
+
+#[test]
+fn genesis_should_continue_in_the_partial_set() {
+	new_test_ext_with_ids((1..71).collect()).execute_with(|| {
+		assert_eq!(Indices::next_enum_set(), 1);
+		assert_eq!(Indices::enum_set_len(0), 64);
+		assert_eq!(Indices::enum_set_len(1), 6);
+
+		make_account(71);
+		assert_eq!(Indices::index_of(71), Some(70));
+		assert_eq!(Indices::next_enum_set(), 1);
+		assert_eq!(Indices::enum_set_len(1), 7);
+		assert_eq!(Indices::enum_set_len(2), 0);
+	});
+}
+
+#[test]
+fn ranged_resolve_hint_should_work() {
+	struct Hint;
+	impl ResolveHint<u64, u64> for Hint {
+		fn resolve_hint(who: &u64) -> Option<u64> {
+			if *who == 0 { None } else { Some(*who) }
+		}
+	}
+	frame_support::parameter_types! {
+		pub const Min: u64 = 100;
+		pub const Max: u64 = 164;
+	}
+	type Ranged = RangedResolveHint<Hint, Min, Max>;
+
+	assert_eq!(Ranged::resolve_hint(&3), Some(103));
+	assert_eq!(Ranged::resolve_hint(&100), Some(136));
+	assert_eq!(Ranged::resolve_hint(&163), Some(135));
+	assert_eq!(Ranged::resolve_hint(&0), None);
+	assert_eq!(RangedResolveHint::<Hint, Max, Min>::resolve_hint(&3), None);
+}
+
+#[test]
+fn offset_resolve_hint_should_avoid_leading_byte_collisions() {
+	frame_support::parameter_types! {
+		pub const Two: usize = 2;
+		pub const Seven: usize = 7;
+	}
+	type Simple = SimpleResolveHint<u64, u64>;
+	type Offset = OffsetResolveHint<u64, u64, Two>;
+
+	let (a, b) = (1u64, 1u64 + (1 << 16));
+	assert_eq!(Simple::resolve_hint(&a), Simple::resolve_hint(&b));
+	assert_eq!(Offset::resolve_hint(&a), Some(0));
+	assert_eq!(Offset::resolve_hint(&b), Some(1));
+
+	// A `u64` encodes to eight bytes, so there is only one byte left at offset 7.
+	assert_eq!(OffsetResolveHint::<u64, u64, Seven>::resolve_hint(&a), None);
+}
+
+#[test]
+fn simple_resolve_hint_should_handle_short_encodings() {
+	type Simple = SimpleResolveHint<u64, u64>;
+	assert_eq!(Simple::resolve_hint(&(3 + 2 * 256)), Some(3 + 2 * 256));
+
+	// A `u8` encodes to a single byte.
+	assert_eq!(SimpleResolveHint::<u8, u64>::resolve_hint(&3), None);
+	assert_eq!(SimpleResolveHint::<(), u64>::resolve_hint(&()), None);
+}
+
+#[test]
+fn migration_to_v1_should_split_enum_sets_into_slots() {
+	new_test_ext().execute_with(|| {
+		// the v0 layout: one plain `Vec` of accounts per enum set, without a reverse lookup.
+		<Indices as Store>::EnumSetLen::remove(0);
+		<Indices as Store>::EnumSlot::remove_prefix(0);
+		<Indices as Store>::LiveIndexCount::kill();
+		for who in &[1, 2, 3, 4] {
+			<Indices as Store>::Accounts::remove(who);
+		}
+		<Indices as Store>::EnumSet::insert(0, vec![1, 2, 3, 4, 7]);
+		<Indices as Store>::StorageVersion::put(0);
+
+		Indices::on_initialize(1);
+
+		assert_eq!(<Indices as Store>::StorageVersion::get(), migration::CURRENT_VERSION);
+		assert!(!<Indices as Store>::EnumSet::exists(0));
+		assert!(!<Indices as Store>::MigrationCursor::exists());
+		assert_eq!(Indices::enum_set_len(0), 5);
+		assert_eq!(Indices::enum_set(0), vec![Some(1), Some(2), Some(3), Some(4), Some(7)]);
+		assert_eq!(Indices::live_index_count(), 5);
+		assert_eq!(Indices::lookup_index(4), Some(7));
+		assert_eq!(Indices::index_of(1), Some(0));
+		assert_eq!(Indices::index_of(7), Some(4));
+		assert_eq!(<Indices as Store>::HeldIndices::get(7, 4), Some(4));
+
+		// running again does not change anything.
+		<Indices as Store>::Accounts::insert(3, 4);
+		Indices::on_initialize(2);
+		assert_eq!(Indices::index_of(3), Some(4));
+	});
+}
+
+#[test]
+fn migration_to_v1_should_be_spread_over_blocks() {
+	new_test_ext().execute_with(|| {
+		// the v0 layout of 20 full sets and the empty one being filled, more than can be migrated
+		// in a block.
+		<Indices as Store>::EnumSetLen::remove(0);
+		<Indices as Store>::EnumSlot::remove_prefix(0);
+		<Indices as Store>::LiveIndexCount::kill();
+		for who in &[1, 2, 3, 4] {
+			<Indices as Store>::Accounts::remove(who);
+		}
+		for set_index in 0..20 {
+			let set = (0..64).map(|i| 1000 + set_index * 64 + i).collect::<Vec<u64>>();
+			<Indices as Store>::EnumSet::insert(set_index, set);
+		}
+		<Indices as Store>::NextEnumSet::put(20);
+		<Indices as Store>::StorageVersion::put(0);
+
+		// sets 20 down to 5.
+		Indices::on_initialize(1);
+		assert_eq!(<Indices as Store>::StorageVersion::get(), 0);
+		assert_eq!(Indices::enum_set_len(5), 64);
+		assert_eq!(Indices::enum_set_len(4), 0);
+		assert_eq!(Indices::lookup_index(64 * 5), Some(1000 + 64 * 5));
+		assert_eq!(Indices::index_of(1000 + 64 * 5 - 1), None);
+
+		// the set being filled is migrated already, the sets below are left alone.
+		make_account(5);
+		assert_eq!(Indices::index_of(5), Some(64 * 20));
+		assert_noop!(Indices::claim(Origin::signed(6), 64 * 4), Error::<Runtime>::NotMigrated);
+		assert_noop!(
+			Indices::force_set_index(Origin::ROOT, 6, 64 * 4 + 3),
+			Error::<Runtime>::NotMigrated,
+		);
+
+		// the sets below are looked up in the v0 layout, but their indices can not be changed.
+		let (index, who) = (64 * 4 + 3, 1000 + 64 * 4 + 3);
+		assert_eq!(Indices::lookup_index(index), Some(who));
+		assert_eq!(Indices::lookup_address(address::Address::Index(index)), Some(who));
+		assert!(Indices::is_index_assigned(index));
+		assert!(!Indices::is_index_assigned(64 * 21));
+		assert_eq!(Indices::try_lookup_index(64 * 22), Err(LookupIndexError::OutOfRange));
+		assert_noop!(Indices::free(Origin::signed(who), index), Error::<Runtime>::NotMigrated);
+		assert_noop!(
+			Indices::transfer(Origin::signed(who), 6, index),
+			Error::<Runtime>::NotMigrated,
+		);
+		assert_noop!(Indices::force_reclaim(Origin::ROOT, index), Error::<Runtime>::NotMigrated);
+
+		// sets 4 down to 0.
+		Indices::on_initialize(2);
+		assert_eq!(<Indices as Store>::StorageVersion::get(), migration::CURRENT_VERSION);
+		assert!(!<Indices as Store>::MigrationCursor::exists());
+		assert_eq!(Indices::index_of(1000), Some(0));
+		assert_eq!(Indices::index_of(1000 + 64 * 5 - 1), Some(64 * 5 - 1));
+		assert_eq!(Indices::live_index_count(), 64 * 20 + 1);
+		assert_ok!(Indices::force_set_index(Origin::ROOT, 6, 64 * 4 + 3));
+	});
+}
+
+#[test]
+fn migration_to_v1_should_keep_indices_of_another_enum_set_size() {
+	new_test_ext().execute_with(|| {
+		<Indices as Store>::EnumSetLen::remove(0);
+		<Indices as Store>::EnumSlot::remove_prefix(0);
+		<Indices as Store>::LiveIndexCount::kill();
+		for who in &[1, 2, 3, 4] {
+			<Indices as Store>::Accounts::remove(who);
+		}
+		// the v0 layout of a full set and the set being filled, with the v0 size of 64.
+		let set = (0..64).map(|i| 1000 + i).collect::<Vec<u64>>();
+		<Indices as Store>::EnumSet::insert(0, set);
+		<Indices as Store>::EnumSet::insert(1, vec![7]);
+		<Indices as Store>::NextEnumSet::put(1);
+		<Indices as Store>::StorageVersion::put(0);
+		set_enum_set_size(16);
+
+		// the v0 layout is looked up by its own size before the migration.
+		assert_eq!(Indices::lookup_index(64), Some(7));
+		assert_eq!(Indices::try_lookup_index(128), Err(LookupIndexError::OutOfRange));
+
+		// index 64 of account 7 moves to the first slot of set 4, instead of slot 0 of set 1.
+		Indices::on_initialize(1);
+		assert_eq!(<Indices as Store>::StorageVersion::get(), migration::CURRENT_VERSION);
+		assert_eq!(Indices::next_enum_set(), 4);
+		assert_eq!(Indices::enum_set_len(3), 16);
+		assert_eq!(Indices::enum_set_len(4), 1);
+		assert_eq!(Indices::enum_slot(4, 0), Some(7));
+		assert_eq!(Indices::lookup_index(64), Some(7));
+		assert_eq!(Indices::index_of(7), Some(64));
+		assert_eq!(Indices::lookup_index(17), Some(1017));
+		assert_eq!(Indices::live_index_count(), 65);
+		let expected = (0..64).map(|i| (i, 1000 + i)).chain(Some((64, 7))).collect::<Vec<_>>();
+		assert_eq!(Indices::enumerate().collect::<Vec<_>>(), expected);
+
+		assert_ok!(Indices::claim(Origin::signed(5), 65));
+		assert_eq!(Indices::enum_set_len(4), 2);
+	});
+}
+
+#[test]
+fn migration_to_v1_should_split_indices_of_enum_sets_across_blocks() {
+	new_test_ext().execute_with(|| {
+		<Indices as Store>::EnumSetLen::remove(0);
+		<Indices as Store>::EnumSlot::remove_prefix(0);
+		<Indices as Store>::LiveIndexCount::kill();
+		for who in &[1, 2, 3, 4] {
+			<Indices as Store>::Accounts::remove(who);
+		}
+		// the v0 layout of 17 full sets and the set being filled, with a size of 64.
+		for set_index in 0..17 {
+			let set = (0..64).map(|i| 1000 + set_index * 64 + i).collect::<Vec<u64>>();
+			<Indices as Store>::EnumSet::insert(set_index, set);
+		}
+		<Indices as Store>::EnumSet::insert(17, vec![7]);
+		<Indices as Store>::NextEnumSet::put(17);
+		<Indices as Store>::StorageVersion::put(0);
+		set_enum_set_size(48);
+
+		// v0 sets 17 down to 2, which leaves indices below 128 unmigrated.
+		Indices::on_initialize(1);
+		assert_eq!(<Indices as Store>::StorageVersion::get(), 0);
+		assert_eq!(Indices::next_enum_set(), 22);
+		assert_eq!(Indices::enum_set_len(22), 17 * 64 + 1 - 22 * 48);
+		assert_eq!(Indices::lookup_index(17 * 64), Some(7));
+		make_account(5);
+		assert_eq!(Indices::index_of(5), Some(17 * 64 + 1));
+
+		// set 2 holds the indices 96 to 143: the ones below 128 are looked up in the v0 layout,
+		// the others were migrated, and none of them can be changed yet.
+		assert_eq!(Indices::lookup_index(100), Some(1100));
+		assert_eq!(Indices::index_of(1100), None);
+		assert_eq!(Indices::lookup_index(130), Some(1130));
+		assert_eq!(Indices::index_of(1130), Some(130));
+		assert!(Indices::is_index_assigned(100));
+		assert!(Indices::is_index_assigned(130));
+		assert_noop!(Indices::free(Origin::signed(1130), 130), Error::<Runtime>::NotMigrated);
+		assert_ok!(Indices::free(Origin::signed(1144), 144));
+
+		// v0 sets 1 and 0.
+		Indices::on_initialize(2);
+		assert_eq!(<Indices as Store>::StorageVersion::get(), migration::CURRENT_VERSION);
+		assert_eq!(Indices::lookup_index(100), Some(1100));
+		assert_eq!(Indices::index_of(1100), Some(100));
+		assert_eq!((0..22).map(Indices::enum_set_len).collect::<Vec<_>>(), vec![48; 22]);
+		assert_eq!(Indices::live_index_count(), 17 * 64 + 1);
+		assert_eq!(Indices::enumerate().count(), 17 * 64 + 1);
+		assert_ok!(Indices::free(Origin::signed(1130), 130));
+	});
+}
+
+#[test]
+fn lookup_should_only_read_a_single_slot() {
+	new_test_ext().execute_with(|| {
+		for i in 5..70 {
+			make_account(i);
+		}
+
+		// a slot only holds the encoding of one account, however big its enum set is.
+		let key = <Indices as Store>::EnumSlot::hashed_key_for(0, 2);
+		assert_eq!(unhashed::get_raw(&key), Some(3u64.encode()));
+		assert_eq!(Indices::enum_set_len(0), 64);
+		assert_eq!(Indices::lookup_index(2), Some(3));
+	});
+}
+
+#[test]
+fn compact_address_should_prefer_the_index() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Indices::address_for_index(3), address::Address::Index(3));
+		assert_eq!(Indices::address_for_id(7), address::Address::Id(7));
+
+		assert_eq!(Indices::compact_address(&2), address::Address::Index(1));
+		assert_eq!(Indices::compact_address(&7), address::Address::Id(7));
+
+		assert_ok!(Indices::free(Origin::signed(2), 1));
+		assert_eq!(Indices::compact_address(&2), address::Address::Id(2));
+	});
+}
+
+#[test]
+fn calls_should_report_their_weights() {
+	let weights = vec![
+		Call::<Runtime>::claim(4).get_dispatch_info().weight,
+		Call::<Runtime>::free(1).get_dispatch_info().weight,
+		Call::<Runtime>::transfer(3, 1).get_dispatch_info().weight,
+		Call::<Runtime>::force_set_index(3, 1).get_dispatch_info().weight,
+		Call::<Runtime>::force_reclaim(1).get_dispatch_info().weight,
+	];
+	assert_eq!(weights, vec![
+		Indices::CLAIM_WEIGHT,
+		Indices::FREE_WEIGHT,
+		Indices::TRANSFER_WEIGHT,
+		Indices::FORCE_SET_INDEX_WEIGHT,
+		Indices::FORCE_RECLAIM_WEIGHT,
+	]);
+
+	let mut distinct = weights.clone();
+	distinct.sort();
+	distinct.dedup();
+	assert_eq!(distinct.len(), weights.len());
+	assert!(weights.iter().all(|w| *w > 0));
+}
+
+#[test]
+fn freed_indices_should_be_reused_before_new_ones() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::free(Origin::signed(2), 1));
+		assert_ok!(Indices::free(Origin::signed(3), 2));
+		assert_eq!(Indices::free_indices(), vec![1, 2]);
+
+		make_account(5);
+		make_account(6);
+		make_account(7);
+		assert_eq!(Indices::lookup_index(2), Some(5));
+		assert_eq!(Indices::lookup_index(1), Some(6));
+		assert_eq!(Indices::lookup_index(4), Some(7));
+		assert!(Indices::free_indices().is_empty());
+		assert_eq!(Indices::live_index_count(), 5);
+		assert_eq!(events()[2..], [
+			MetaEvent::indices(RawEvent::NewAccountIndex(5, 2)),
+			MetaEvent::indices(RawEvent::NewAccountIndex(6, 1)),
+			MetaEvent::indices(RawEvent::NewAccountIndex(7, 4)),
+		]);
+	});
+}
+
+#[test]
+fn claimed_free_indices_should_be_removed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::free(Origin::signed(2), 1));
+		assert_ok!(Indices::claim(Origin::signed(4), 1));
+		assert!(Indices::free_indices().is_empty());
+
+		make_account(6);
+		assert_eq!(Indices::lookup_index(1), Some(4));
+		assert_eq!(Indices::lookup_index(4), Some(6));
+		assert!(Indices::free_indices().is_empty());
+	});
+}
+
+#[test]
+fn dead_indices_should_be_reclaimed_in_bounded_steps() {
+	new_test_ext().execute_with(|| {
+		for i in 5..21 {
+			make_account(i);
+		}
+		kill_account(3);
+		kill_account(14);
+		<Indices as Store>::ReclaimCursor::put(0);
+
+		// the first scan finds the dead account at index 2.
+		make_account(100);
+		assert_eq!(Indices::lookup_index(2), Some(100));
+		assert_eq!(Indices::reclaim_cursor(), 3);
+
+		// at most `MaxReclaimSteps` slots are inspected, index 13 is not reached by this scan.
+		make_account(101);
+		assert_eq!(Indices::lookup_index(20), Some(101));
+		assert_eq!(Indices::reclaim_cursor(), 11);
+
+		// but by the next one.
+		make_account(102);
+		assert_eq!(Indices::lookup_index(13), Some(102));
+		assert!(events().contains(&MetaEvent::indices(RawEvent::IndexReclaimed(14, 102, 13, 1))));
+	});
+}
+
+#[test]
+fn unlookup_should_return_the_index_form() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Indices::unlookup(2), address::Address::Index(1));
+		assert_eq!(Indices::unlookup(7), address::Address::Id(7));
+
+		for who in &[2, 7] {
+			assert_eq!(Indices::lookup(Indices::unlookup(*who)).ok(), Some(*who));
+		}
+	});
+}
+
+#[test]
+fn enum_set_size_hint_should_locate_the_slot_of_an_index() {
+	new_test_ext().execute_with(|| {
+		for who in 10..80 {
+			make_account(who);
+		}
+
+		let size = Indices::enum_set_size_hint();
+		assert_eq!(size, <Runtime as Trait>::EnumSetSize::get() as u64);
+		for index in &[0, 3, 63, 64, 69] {
+			assert!(Indices::lookup_index(*index).is_some());
+			let slot = Indices::enum_slot(index / size, (index % size) as u32);
+			assert_eq!(slot, Indices::lookup_index(*index));
+		}
+	});
+}
+
+#[test]
+fn index_location_should_split_at_set_boundaries() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Indices::index_location(0), (0, 0));
+		assert_eq!(Indices::index_location(63), (0, 63));
+		assert_eq!(Indices::index_location(64), (1, 0));
+		assert_eq!(Indices::index_location(65), (1, 1));
+		assert_eq!(Indices::index_location(u64::max_value()), (u64::max_value() / 64, 63));
+	});
+}
+
+#[test]
+fn join_index_should_not_overflow() {
+	new_test_ext().execute_with(|| {
+		let last_set = u64::max_value() / 64;
+		assert_eq!(Indices::join_index(1, 5), Some(69));
+		assert_eq!(Indices::join_index(last_set, 63), Some(u64::max_value()));
+		assert_eq!(Indices::join_index(last_set, 64), None);
+		assert_eq!(Indices::join_index(last_set + 1, 0), None);
+
+		for index in &[0, 69, u64::max_value() - 64, u64::max_value()] {
+			let (set_index, item_index) = Indices::split_index(*index).unwrap();
+			assert_eq!(Indices::join_index(set_index, item_index), Some(*index));
+		}
+	});
+}
+
+#[test]
+fn nearly_exhausted_index_space_should_be_reported_once() {
+	new_test_ext().execute_with(|| {
+		// pretend all but the last 12 indices are taken.
+		let last_set = u64::max_value() / 64;
+		<NextEnumSet<Runtime>>::put(last_set);
+		<EnumSetLen<Runtime>>::insert(last_set, 52);
+
+		for who in 10..20 {
+			make_account(who);
+		}
+
+		let warnings = events().into_iter()
+			.filter(|e| e == &MetaEvent::indices(RawEvent::IndexSpaceNearlyExhausted(8)))
+			.count();
+		assert_eq!(warnings, 1);
+		assert_eq!(Indices::index_of(13), Some(u64::max_value() - 8));
+	});
+}
+
+#[test]
+fn nearly_exhausted_index_space_should_be_reported_for_claims() {
+	new_test_ext().execute_with(|| {
+		// pretend all but the last 12 indices are taken.
+		let last_set = u64::max_value() / 64;
+		<NextEnumSet<Runtime>>::put(last_set);
+		<EnumSetLen<Runtime>>::insert(last_set, 52);
+
+		// claims reach the threshold.
+		for who in 10..14 {
+			assert_ok!(Indices::claim(Origin::signed(who), u64::max_value() - 21 + who));
+		}
+		assert!(Indices::index_space_warned());
+		assert_eq!(
+			events().last(),
+			Some(&MetaEvent::indices(RawEvent::IndexSpaceNearlyExhausted(8))),
+		);
+
+		// later assignments, of any kind, do not warn again.
+		assert_ok!(Indices::force_set_index(Origin::ROOT, 15, u64::max_value() - 2));
+		make_account(16);
+		assert_eq!(Indices::index_of(16), Some(u64::max_value() - 1));
+		let warnings = events().into_iter()
+			.filter(|e| match e {
+				MetaEvent::indices(RawEvent::IndexSpaceNearlyExhausted(_)) => true,
+				_ => false,
+			})
+			.count();
+		assert_eq!(warnings, 1);
+	});
+}
+
+#[test]
+fn nearly_exhausted_index_space_should_be_reported_when_skipped_by_force() {
+	new_test_ext().execute_with(|| {
+		let last_set = u64::max_value() / 64;
+		<NextEnumSet<Runtime>>::put(last_set);
+		<EnumSetLen<Runtime>>::insert(last_set, 52);
+
+		// the index at the threshold is left out.
+		assert_ok!(Indices::force_set_index(Origin::ROOT, 10, u64::max_value() - 5));
+		assert_eq!(
+			events().last(),
+			Some(&MetaEvent::indices(RawEvent::IndexSpaceNearlyExhausted(5))),
+		);
+	});
+}
+
+#[test]
+fn exhausted_index_space_should_not_wrap_around() {
+	new_test_ext().execute_with(|| {
+		// pretend all but the last 2 indices are taken.
+		let last_set = u64::max_value() / 64;
+		<NextEnumSet<Runtime>>::put(last_set);
+		<EnumSetLen<Runtime>>::insert(last_set, 62);
+
+		make_account(10);
+		make_account(11);
+		assert_eq!(Indices::index_of(11), Some(u64::max_value()));
+		assert_eq!(Indices::next_enum_set(), last_set + 1);
+
+		let before = events().len();
+		make_account(12);
+		make_account(13);
+		assert_eq!(Indices::index_of(12), None);
+		assert_eq!(Indices::index_of(13), None);
+		assert_eq!(events()[before..], [
+			MetaEvent::indices(RawEvent::IndexSpaceExhausted(12)),
+			MetaEvent::indices(RawEvent::IndexSpaceExhausted(13)),
+		]);
+
+		// the low indices are untouched.
+		assert_eq!(Indices::lookup_index(0), Some(1));
+		assert_eq!(Indices::enum_set_len(0), 4);
+		assert_eq!(Indices::live_index_count(), 6);
+
+		// a freed index is still handed out.
+		assert_ok!(Indices::free(Origin::signed(10), u64::max_value() - 1));
+		make_account(14);
+		assert_eq!(Indices::index_of(14), Some(u64::max_value() - 1));
+	});
+}
+
+#[test]
+fn genesis_dead_ids_should_be_reclaimed() {
+	new_test_ext_with_dead_ids(vec![(6, 10), (4, 11)]).execute_with(|| {
+		assert_eq!(Indices::lookup_index(4), Some(11));
+		assert_eq!(Indices::lookup_index(5), None);
+		assert_eq!(Indices::lookup_index(6), Some(10));
+		assert_eq!(Indices::index_of(10), Some(6));
+		assert_eq!(Indices::enum_set_len(0), 7);
+		assert_eq!(Indices::live_index_count(), 6);
+		assert!(!Indices::is_reserved(5));
+
+		// the first new account takes the first dead index instead of a new one.
+		make_account(12);
+		assert_eq!(Indices::index_of(12), Some(4));
+		assert_eq!(Indices::index_of(11), None);
+		assert_eq!(events(), vec![MetaEvent::indices(RawEvent::IndexReclaimed(11, 12, 4, 1))]);
+	});
+}
+
+#[test]
+fn lookup_range_should_work() {
+	new_test_ext().execute_with(|| {
+		for who in 10..80 {
+			make_account(who);
+		}
+		assert_ok!(Indices::free(Origin::signed(72), 66));
+
+		// spans the full first set and the partially filled second one.
+		let range = Indices::lookup_range(62, 12);
+		assert_eq!(range.len(), 12);
+		assert_eq!(range[0], (62, Some(68)));
+		assert_eq!(range[1], (63, Some(69)));
+		assert_eq!(range[2], (64, Some(70)));
+		assert_eq!(range[4], (66, None));
+		assert_eq!(range[9], (71, Some(77)));
+		assert_eq!(range[11], (73, Some(79)));
+
+		// past `NextEnumSet` there are no accounts.
+		assert_eq!(Indices::next_enum_set(), 1);
+		let range = Indices::lookup_range(126, 4);
+		assert_eq!(range, vec![(126, None), (127, None), (128, None), (129, None)]);
+
+		assert_eq!(Indices::lookup_range(0, u32::max_value()).len(), 1024);
+		assert_eq!(Indices::lookup_range(u64::max_value() - 1, 5).len(), 2);
+	});
+}
+
+#[test]
+fn can_reclaim_batch_should_work() {
+	new_test_ext().execute_with(|| {
+		for who in 10..80 {
+			make_account(who);
+		}
+		kill_account(20);
+		kill_account(75);
+
+		let candidates = [69, 0, 14, 70, 200, 63, 14, 74, u64::max_value()];
+		assert_eq!(
+			Indices::can_reclaim_batch(&candidates),
+			vec![true, false, true, false, false, false, true, false, false],
+		);
+		for index in &candidates {
+			assert_eq!(Indices::can_reclaim_batch(&[*index]), vec![Indices::can_reclaim(*index)]);
+		}
+		assert!(Indices::can_reclaim_batch(&[]).is_empty());
+	});
+}
+
+/// Index 1 and 3 belong to dead accounts and index 2 is freed. Id 259 hints index 3.
+fn reclaim_with_policy(policy: ReclaimPolicy) -> Option<u64> {
+	new_test_ext().execute_with(|| {
+		set_reclaim_policy(policy);
+		kill_account(2);
+		kill_account(4);
+		assert_ok!(Indices::free(Origin::signed(3), 2));
+
+		make_account(3 + 256);
+		Indices::index_of(259)
+	})
+}
+
+#[test]
+fn reclaim_policies_should_choose_different_indices() {
+	assert_eq!(reclaim_with_policy(ReclaimPolicy::PreferHint), Some(3));
+	assert_eq!(reclaim_with_policy(ReclaimPolicy::PreferFreeList), Some(2));
+	assert_eq!(reclaim_with_policy(ReclaimPolicy::LowestFirst), Some(1));
+}
+
+#[test]
+fn prefer_free_list_should_fall_back_to_the_hint() {
+	new_test_ext().execute_with(|| {
+		set_reclaim_policy(ReclaimPolicy::PreferFreeList);
+		kill_account(4);
+
+		make_account(3 + 256);
+		assert_eq!(Indices::lookup_index(3), Some(259));
+		assert_eq!(Indices::free_indices(), Vec::<u64>::new());
+	});
+}
+
+#[test]
+fn lowest_first_should_not_use_the_free_list() {
+	new_test_ext().execute_with(|| {
+		set_reclaim_policy(ReclaimPolicy::LowestFirst);
+		assert_ok!(Indices::free(Origin::signed(2), 1));
+		assert_ok!(Indices::free(Origin::signed(4), 3));
+
+		make_account(5);
+		make_account(6);
+		assert_eq!(Indices::lookup_index(1), Some(5));
+		assert_eq!(Indices::lookup_index(3), Some(6));
+		// both are dropped from the list once they are taken.
+		assert!(Indices::free_indices().is_empty());
+	});
+}
+
+#[test]
+fn lowest_first_should_take_freed_indices_above_more_live_indices_than_the_steps() {
+	new_test_ext().execute_with(|| {
+		set_reclaim_policy(ReclaimPolicy::LowestFirst);
+		// the indices 4 to 23, far more than `MaxReclaimSteps` above the genesis ones.
+		for who in 10..30 {
+			make_account(who);
+		}
+		assert_eq!(Indices::lookup_index(23), Some(29));
+		assert_eq!(Indices::lowest_reclaim_cursor(), 23);
+
+		assert_ok!(Indices::free(Origin::signed(25), 19));
+		assert_ok!(Indices::free(Origin::signed(15), 9));
+		assert_eq!(Indices::lowest_reclaim_cursor(), 9);
+
+		make_account(100);
+		assert_eq!(Indices::lookup_index(9), Some(100));
+		// index 19 is out of reach of the slots inspected from the cursor.
+		make_account(101);
+		assert_eq!(Indices::lookup_index(19), Some(101));
+		assert_eq!(Indices::lookup_index(24), None);
+		assert!(Indices::free_indices().is_empty());
+
+		// the cursor is not moved below the reserved bound.
+		<LowestReclaimCursor<Runtime>>::kill();
+		<ReservedUpTo<Runtime>>::put(20);
+		make_account(102);
+		assert_eq!(Indices::lookup_index(24), Some(102));
+		assert_eq!(Indices::lowest_reclaim_cursor(), 24);
+	});
+}
+
+#[test]
+fn free_list_should_be_relinked_when_an_entry_is_taken() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::free(Origin::signed(2), 1));
+		assert_ok!(Indices::free(Origin::signed(3), 2));
+		assert_ok!(Indices::free(Origin::signed(4), 3));
+		assert_eq!(Indices::free_indices(), vec![1, 2, 3]);
+		assert_eq!(Indices::last_free_index(), Some(3));
+
+		// from the middle.
+		assert_ok!(Indices::claim(Origin::signed(5), 2));
+		assert_eq!(Indices::free_indices(), vec![1, 3]);
+		let entry = FreeListEntry { older: None, newer: Some(3) };
+		assert_eq!(Indices::free_list_entry(1), Some(entry));
+		assert_eq!(Indices::free_list_entry(2), None);
+
+		// from the end.
+		assert_ok!(Indices::claim(Origin::signed(6), 3));
+		assert_eq!(Indices::free_indices(), vec![1]);
+		assert_eq!(Indices::last_free_index(), Some(1));
+		assert_eq!(Indices::free_list_entry(1), Some(FreeListEntry { older: None, newer: None }));
+
+		// and the last one.
+		make_account(7);
+		assert_eq!(Indices::lookup_index(1), Some(7));
+		assert_eq!(Indices::last_free_index(), None);
+		assert!(!<FreeList<Runtime>>::exists(1));
+	});
+}
+
+#[test]
+fn free_indices_should_not_grow_across_repeated_free_and_claim() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..10 {
+			assert_ok!(Indices::free(Origin::signed(2), 1));
+			assert_eq!(Indices::free_indices(), vec![1]);
+			assert_ok!(Indices::claim(Origin::signed(2), 1));
+			assert!(Indices::free_indices().is_empty());
+		}
+
+		// the same holds for indices taken by force or by new accounts.
+		set_reclaim_policy(ReclaimPolicy::LowestFirst);
+		for i in 0..10 {
+			assert_ok!(Indices::free(Origin::signed(2), 1));
+			if i % 2 == 0 {
+				assert_ok!(Indices::force_set_index(Origin::ROOT, 2, 1));
+			} else {
+				make_account(2);
+			}
+			assert_eq!(Indices::lookup_index(1), Some(2));
+			assert!(Indices::free_indices().is_empty());
+		}
+	});
+}
+
+#[test]
+fn repeated_lookups_should_hit_the_cache() {
+	new_test_ext().execute_with(|| {
+		Indices::with_lookup_cache(|| {
+			assert_eq!(Indices::lookup_index(1), Some(2));
+			assert_eq!(Indices::lookup_index(7), None);
+
+			// writes that bypass the module are not seen while the cache is in scope.
+			<EnumSlot<Runtime>>::insert(0, 1, 7);
+			<EnumSlot<Runtime>>::insert(0, 7, 8);
+			assert_eq!(Indices::lookup_index(1), Some(2));
+			assert_eq!(Indices::lookup_index(7), None);
+			Indices::with_lookup_cache(|| assert_eq!(Indices::lookup_index(1), Some(2)));
+		});
+
+		assert_eq!(Indices::lookup_index(1), Some(7));
+		assert_eq!(Indices::lookup_index(7), Some(8));
+	});
+}
+
+#[test]
+fn mutations_should_invalidate_the_cache() {
+	new_test_ext().execute_with(|| {
+		Indices::with_lookup_cache(|| {
+			assert_eq!(Indices::lookup_index(1), Some(2));
+			assert_ok!(Indices::transfer(Origin::signed(2), 5, 1));
+			assert_eq!(Indices::lookup_index(1), Some(5));
+
+			assert_ok!(Indices::free(Origin::signed(5), 1));
+			assert_eq!(Indices::lookup_index(1), None);
+
+			make_account(1 + 256);
+			assert_eq!(Indices::lookup_index(1), Some(257));
+
+			assert_eq!(Indices::lookup_index(4), None);
+			make_account(6);
+			assert_eq!(Indices::lookup_index(4), Some(6));
+
+			assert_ok!(Indices::claim(Origin::signed(7), 5));
+			assert_eq!(Indices::lookup_index(5), Some(7));
+
+			assert_ok!(Indices::force_set_index(Origin::ROOT, 8, 5));
+			assert_eq!(Indices::lookup_index(5), Some(8));
+
+			assert_ok!(Indices::force_reclaim(Origin::ROOT, 5));
+			assert_eq!(Indices::lookup_index(5), None);
+		});
+	});
+}
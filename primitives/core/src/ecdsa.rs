@@ -37,6 +37,7 @@ use crate::crypto::Ss58Codec;
 #[cfg(feature = "std")]
 use serde::{de, Serializer, Serialize, Deserializer, Deserialize};
 use crate::crypto::{Public as TraitPublic, UncheckedFrom, CryptoType, Derive};
+use sp_runtime_interface::pass_by::{PassByInner, PassByCodec};
 #[cfg(feature = "full_crypto")]
 use secp256k1::{PublicKey, SecretKey};
 
@@ -47,7 +48,10 @@ use secp256k1::{PublicKey, SecretKey};
 type Seed = [u8; 32];
 
 /// The ECDSA public key.
-#[derive(Clone, Encode, Decode)]
+///
+/// As it is either a full or a compressed key, it is passed SCALE encoded through the runtime
+/// interface.
+#[derive(Clone, Encode, Decode, PassByCodec)]
 pub enum Public {
 	/// A full raw ECDSA public key.
 	Full([u8; 64]),
@@ -236,7 +240,7 @@ impl sp_std::hash::Hash for Public {
 }
 
 /// A signature (a 512-bit value, plus 8 bits for recovery ID).
-#[derive(Encode, Decode)]
+#[derive(Encode, Decode, PassByInner)]
 pub struct Signature([u8; 65]);
 
 impl sp_std::convert::TryFrom<&[u8]> for Signature {
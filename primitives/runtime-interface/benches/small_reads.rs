@@ -0,0 +1,98 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks decoding small arguments from the wasm memory on the host.
+//!
+//! Run with and without the `smallvec` feature to compare. The number of allocations per read is
+//! printed before the benchmark runs.
+
+use criterion::{Criterion, criterion_group, criterion_main, black_box};
+use sp_runtime_interface::{
+	codec::Encode, host::FromFFIValue,
+	sp_wasm_interface::{FunctionContext, Pointer, Result, Sandbox, WordSize},
+};
+use std::{alloc::{GlobalAlloc, Layout, System}, sync::atomic::{AtomicUsize, Ordering}};
+
+/// Counts the allocations of the process.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		System.alloc(layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// A context that only supports reading its memory.
+struct ReadContext(Vec<u8>);
+
+impl FunctionContext for ReadContext {
+	fn read_memory_into(&self, address: Pointer<u8>, dest: &mut [u8]) -> Result<()> {
+		let start = u32::from(address) as usize;
+		dest.copy_from_slice(&self.0[start..start + dest.len()]);
+		Ok(())
+	}
+
+	fn memory_size(&self) -> WordSize {
+		self.0.len() as WordSize
+	}
+
+	fn write_memory(&mut self, _: Pointer<u8>, _: &[u8]) -> Result<()> {
+		unimplemented!()
+	}
+
+	fn allocate_memory(&mut self, _: WordSize) -> Result<Pointer<u8>> {
+		unimplemented!()
+	}
+
+	fn deallocate_memory(&mut self, _: Pointer<u8>) -> Result<()> {
+		unimplemented!()
+	}
+
+	fn sandbox(&mut self) -> &mut dyn Sandbox {
+		unimplemented!()
+	}
+}
+
+fn bench_small_reads(c: &mut Criterion) {
+	let data = Some([7u8; 32]).encode();
+	// the data is at the start of the memory.
+	let arg = (data.len() as u64) << 32;
+	let mut context = ReadContext(data);
+
+	let before = ALLOCATIONS.load(Ordering::Relaxed);
+	for _ in 0..1000 {
+		black_box(Option::<[u8; 32]>::from_ffi_value(&mut context, arg).unwrap());
+	}
+	let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+	println!("Allocations per read of `Option<[u8; 32]>`: {}", allocations as f64 / 1000.0);
+
+	c.bench_function("read `Option<[u8; 32]>`", move |b| {
+		b.iter(|| Option::<[u8; 32]>::from_ffi_value(&mut context, black_box(arg)).unwrap())
+	});
+}
+
+criterion_group!(benches, bench_small_reads);
+criterion_main!(benches);
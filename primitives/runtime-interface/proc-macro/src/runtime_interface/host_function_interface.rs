@@ -25,10 +25,12 @@ use crate::utils::{
 	get_function_argument_types_without_ref, get_function_argument_types_ref_and_mut,
 	get_function_argument_names_and_types_without_ref, get_trait_methods, get_function_arguments,
 	get_function_argument_types, create_exchangeable_host_function_ident,
+	get_mutable_reference_arguments, function_argument_count, create_host_function_alias_idents,
+	is_borrowed_byte_slice,
 };
 
 use syn::{
-	ItemTrait, TraitItemMethod, Result, ReturnType, Ident, TraitItem, Pat, Error, Signature,
+	ItemTrait, TraitItemMethod, Result, ReturnType, Ident, Pat, Error, Signature,
 	spanned::Spanned,
 };
 
@@ -40,13 +42,21 @@ use inflector::Inflector;
 
 use std::iter::{Iterator, self};
 
+use super::{FunctionVersions, FunctionAliases, PureFunctions};
+
 /// Generate the extern host functions for wasm and the `HostFunctions` struct that provides the
 /// implementations for the host functions on the host.
-pub fn generate(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream> {
+pub fn generate(
+	trait_def: &ItemTrait,
+	versions: &FunctionVersions,
+	aliases: &FunctionAliases,
+	pure_functions: &PureFunctions,
+	is_wasm_only: bool,
+) -> Result<TokenStream> {
 	let trait_name = &trait_def.ident;
 	let extern_host_function_impls = get_trait_methods(trait_def)
 		.try_fold(TokenStream::new(), |mut t, m| {
-			t.extend(generate_extern_host_function(m, trait_name)?);
+			t.extend(generate_extern_host_function(m, versions, trait_name)?);
 			Ok::<_, Error>(t)
 		})?;
 	let exchangeable_host_functions = get_trait_methods(trait_def)
@@ -54,7 +64,13 @@ pub fn generate(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream
 			t.extend(generate_exchangeable_host_function(m)?);
 			Ok::<_, Error>(t)
 		})?;
-	let host_functions_struct = generate_host_functions_struct(trait_def, is_wasm_only)?;
+	let host_functions_struct = generate_host_functions_struct(
+		trait_def,
+		versions,
+		aliases,
+		pure_functions,
+		is_wasm_only,
+	)?;
 
 	Ok(
 		quote! {
@@ -76,7 +92,11 @@ pub fn generate(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream
 }
 
 /// Generate the extern host function for the given method.
-fn generate_extern_host_function(method: &TraitItemMethod, trait_name: &Ident) -> Result<TokenStream> {
+fn generate_extern_host_function(
+	method: &TraitItemMethod,
+	versions: &FunctionVersions,
+	trait_name: &Ident,
+) -> Result<TokenStream> {
 	let crate_ = generate_crate_access();
 	let args = get_function_arguments(&method.sig);
 	let arg_types = get_function_argument_types_without_ref(&method.sig);
@@ -85,7 +105,8 @@ fn generate_extern_host_function(method: &TraitItemMethod, trait_name: &Ident) -
 	let arg_names2 = get_function_argument_names(&method.sig);
 	let arg_names3 = get_function_argument_names(&method.sig);
 	let function = &method.sig.ident;
-	let ext_function = create_host_function_ident(&method.sig.ident, trait_name);
+	let (name, version) = &versions[function];
+	let ext_function = create_host_function_ident(name, *version, trait_name);
 	let doc_string = format!(
 		" Default extern host function implementation for [`super::{}`].",
 		method.sig.ident,
@@ -155,17 +176,33 @@ fn generate_exchangeable_host_function(method: &TraitItemMethod) -> Result<Token
 
 /// Generate the `HostFunctions` struct that implements `wasm-interface::HostFunctions` to provide
 /// implementations for the extern host functions.
-fn generate_host_functions_struct(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream> {
+fn generate_host_functions_struct(
+	trait_def: &ItemTrait,
+	versions: &FunctionVersions,
+	aliases: &FunctionAliases,
+	pure_functions: &PureFunctions,
+	is_wasm_only: bool,
+) -> Result<TokenStream> {
 	let crate_ = generate_crate_access();
-	let host_functions = trait_def
-		.items
-		.iter()
-		.filter_map(|i| match i {
-			TraitItem::Method(ref method) => Some(method),
-			_ => None,
-		})
-		.map(|m| generate_host_function_implementation(&trait_def.ident, m, is_wasm_only))
-		.collect::<Result<Vec<_>>>()?;
+	let trait_name = &trait_def.ident;
+	let mut host_functions = Vec::new();
+	for method in get_trait_methods(trait_def) {
+		let (name, version) = &versions[&method.sig.ident];
+		let old_names = aliases.get(&method.sig.ident).map_or(&[][..], |n| &n[..]);
+		let is_pure = pure_functions.contains(&method.sig.ident);
+
+		for ident in create_host_function_alias_idents(name, old_names, *version, trait_name) {
+			host_functions.push(
+				generate_host_function_implementation(
+					trait_name,
+					method,
+					&ident,
+					is_pure,
+					is_wasm_only,
+				)?
+			);
+		}
+	}
 
 	Ok(
 		quote! {
@@ -191,9 +228,11 @@ fn generate_host_functions_struct(trait_def: &ItemTrait, is_wasm_only: bool) ->
 fn generate_host_function_implementation(
 	trait_name: &Ident,
 	method: &TraitItemMethod,
+	host_function_ident: &Ident,
+	is_pure: bool,
 	is_wasm_only: bool,
 ) -> Result<TokenStream> {
-	let name = create_host_function_ident(&method.sig.ident, trait_name).to_string();
+	let name = host_function_ident.to_string();
 	let struct_name = Ident::new(&name.to_pascal_case(), Span::call_site());
 	let crate_ = generate_crate_access();
 	let signature = generate_wasm_interface_signature_for_host_function(&method.sig)?;
@@ -201,8 +240,13 @@ fn generate_host_function_implementation(
 		&method.sig,
 		trait_name,
 	).collect::<Result<Vec<_>>>()?;
-	let ffi_to_host_values = generate_ffi_to_host_value(&method.sig).collect::<Result<Vec<_>>>()?;
-	let host_function_call = generate_host_function_call(&method.sig, is_wasm_only);
+	let arity_check = generate_arity_check(&method.sig);
+	let ffi_to_host_values = generate_ffi_to_host_value(
+		&method.sig,
+		trait_name,
+		is_wasm_only,
+	).collect::<Result<Vec<_>>>()?;
+	let host_function_call = generate_host_function_call(&method.sig, is_wasm_only)?;
 	let into_preallocated_ffi_value = generate_into_preallocated_ffi_value(&method.sig)?;
 	let convert_return_value = generate_return_value_into_wasm_value(&method.sig);
 
@@ -227,11 +271,16 @@ fn generate_host_function_implementation(
 						args: &mut dyn Iterator<Item = #crate_::sp_wasm_interface::Value>,
 					) -> std::result::Result<Option<#crate_::sp_wasm_interface::Value>, String> {
 						#( #wasm_to_ffi_values )*
+						#arity_check
 						#( #ffi_to_host_values )*
 						#host_function_call
 						#into_preallocated_ffi_value
 						#convert_return_value
 					}
+
+					fn is_pure(&self) -> bool {
+						#is_pure
+					}
 				}
 
 				&#struct_name as &dyn #crate_::sp_wasm_interface::Function
@@ -273,10 +322,7 @@ fn generate_wasm_to_ffi_values<'a>(
 ) -> impl Iterator<Item = Result<TokenStream>> + 'a {
 	let crate_ = generate_crate_access();
 	let function_name = &sig.ident;
-	let error_message = format!(
-		"Number of arguments given to `{}` does not match the expected number of arguments!",
-		function_name,
-	);
+	let error_message = arity_error_message(sig);
 
 	get_function_argument_names_and_types_without_ref(sig)
 		.map(move |(name, ty)| {
@@ -298,39 +344,85 @@ fn generate_wasm_to_ffi_values<'a>(
 		})
 }
 
+/// The error message returned when the wrong number of wasm values is given to `sig`.
+fn arity_error_message(sig: &Signature) -> String {
+	format!(
+		"Number of arguments given to `{}` does not match the expected number of {} arguments!",
+		sig.ident,
+		function_argument_count(sig),
+	)
+}
+
+/// Generate the code that checks that no wasm values are left after taking the arguments of `sig`.
+fn generate_arity_check(sig: &Signature) -> TokenStream {
+	let error_message = arity_error_message(sig);
+
+	quote! {
+		if args.next().is_some() {
+			return Err(#error_message.into())
+		}
+	}
+}
+
 /// Generate the code to convert the ffi values on the host to the host values using `FromFFIValue`.
+///
+/// A panic of the conversion is returned as an error, instead of unwinding into the executor.
+/// Arguments that are borrowed with `WithFFIValue` are skipped, see
+/// [`generate_host_function_call`].
 fn generate_ffi_to_host_value<'a>(
 	sig: &'a Signature,
+	trait_name: &'a Ident,
+	is_wasm_only: bool,
 ) -> impl Iterator<Item = Result<TokenStream>> + 'a {
 	let mut_access = get_function_argument_types_ref_and_mut(sig);
 	let crate_ = generate_crate_access();
+	let function_name = &sig.ident;
 
 	get_function_argument_names_and_types_without_ref(sig)
 		.zip(mut_access.map(|v| v.and_then(|m| m.1)))
-		.map(move |((name, ty), mut_access)| {
+		.zip(get_function_argument_types(sig))
+		.filter(move |(_, ty)| is_wasm_only || !is_borrowed_byte_slice(ty))
+		.map(move |(((name, ty), mut_access), _)| {
 			let ffi_value_var_name = generate_ffi_value_var_name(&name)?;
+			let what = format!(
+				"Converting `{}` from its ffi value while executing `{}` from interface `{}`",
+				name.to_token_stream(),
+				function_name,
+				trait_name,
+			);
 
 			Ok(
 				quote! {
-					let #mut_access #name = <#ty as #crate_::host::FromFFIValue>::from_ffi_value(
-						__function_context__,
-						#ffi_value_var_name,
-					)?;
+					let #mut_access #name = #crate_::host::catch_ffi_value_panic(#what, || {
+						<#ty as #crate_::host::FromFFIValue>::from_ffi_value(
+							__function_context__,
+							#ffi_value_var_name,
+						)
+					})?;
 				}
 			)
 		})
 }
 
 /// Generate the code to call the host function and the ident that stores the result.
-fn generate_host_function_call(sig: &Signature, is_wasm_only: bool) -> TokenStream {
+///
+/// Arguments given as `&[u8]` are borrowed from the wasm memory using `WithFFIValue`, so the host
+/// function is called from within `with_ffi_value`. This is not done for wasm only interfaces, as
+/// their host function requires mutable access to the function context.
+fn generate_host_function_call(sig: &Signature, is_wasm_only: bool) -> Result<TokenStream> {
+	let crate_ = generate_crate_access();
 	let host_function_name = &sig.ident;
 	let result_var_name = generate_host_function_result_var_name(&sig.ident);
-	let ref_and_mut = get_function_argument_types_ref_and_mut(sig).map(|ram|
-		ram.map(|(vr, vm)| quote!(#vr #vm))
-	);
-	let names = get_function_argument_names(sig);
-
-	let var_access = names.zip(ref_and_mut)
+	let is_borrowed = get_function_argument_types(sig)
+		.map(|ty| !is_wasm_only && is_borrowed_byte_slice(&ty))
+		.collect::<Vec<_>>();
+	let ref_and_mut = get_function_argument_types_ref_and_mut(sig)
+		.zip(is_borrowed.clone())
+		// A borrowed argument is already a reference.
+		.map(|(ram, is_borrowed)| ram.filter(|_| !is_borrowed).map(|(vr, vm)| quote!(#vr #vm)));
+	let names = get_function_argument_names(sig).collect::<Vec<_>>();
+
+	let var_access = names.iter().zip(ref_and_mut)
 		.map(|(n, ref_and_mut)| {
 			quote!( #ref_and_mut #n )
 		})
@@ -340,9 +432,28 @@ fn generate_host_function_call(sig: &Signature, is_wasm_only: bool) -> TokenStre
 				.take(1)
 		);
 
-	quote! {
-		let #result_var_name = #host_function_name ( #( #var_access ),* );
+	let mut call = quote!( #host_function_name ( #( #var_access ),* ) );
+	let mut borrow_errors = Vec::new();
+
+	// Nest the call into one `with_ffi_value` per borrowed argument, the first one outermost.
+	for (name, _) in names.iter().zip(is_borrowed).filter(|(_, b)| *b).rev() {
+		let ffi_value_var_name = generate_ffi_value_var_name(name)?;
+
+		call = quote! {
+			<[u8] as #crate_::host::WithFFIValue>::with_ffi_value(
+				&*__function_context__,
+				#ffi_value_var_name,
+				|#name| #call,
+			)
+		};
+		borrow_errors.push(quote!( ? ));
 	}
+
+	Ok(
+		quote! {
+			let #result_var_name = #call #( #borrow_errors )*;
+		}
+	)
 }
 
 /// Generate the variable name that stores the result of the host function.
@@ -372,13 +483,8 @@ fn generate_ffi_value_var_name(pat: &Pat) -> Result<Ident> {
 /// that the type implements `IntoPreAllocatedFFIValue`.
 fn generate_into_preallocated_ffi_value(sig: &Signature) -> Result<TokenStream> {
 	let crate_ = generate_crate_access();
-	let ref_and_mut = get_function_argument_types_ref_and_mut(sig).map(|ram|
-		ram.and_then(|(vr, vm)| vm.map(|v| (vr, v)))
-	);
-	let names_and_types = get_function_argument_names_and_types_without_ref(sig);
 
-	ref_and_mut.zip(names_and_types)
-		.filter_map(|(ram, (name, ty))| ram.map(|_| (name, ty)))
+	get_mutable_reference_arguments(sig)
 		.map(|(name, ty)| {
 			let ffi_var_name = generate_ffi_value_var_name(&name)?;
 
@@ -408,7 +514,10 @@ fn generate_return_value_into_wasm_value(sig: &Signature) -> TokenStream {
 				<#ty as #crate_::host::IntoFFIValue>::into_ffi_value(
 					#result_var_name,
 					__function_context__,
-				).map(#crate_::sp_wasm_interface::IntoValue::into_value).map(Some)
+				)
+				.map(#crate_::sp_wasm_interface::IntoValue::into_value)
+				.map(Some)
+				.map_err(Into::into)
 			}
 		}
 	}
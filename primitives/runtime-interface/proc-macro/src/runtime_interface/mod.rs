@@ -14,11 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::utils::generate_runtime_interface_include;
+use crate::utils::{
+	generate_runtime_interface_include, get_function_version, create_function_ident_with_version,
+	get_function_old_names, get_function_is_pure, ensure_pure_signature, VERSION_ATTRIBUTE,
+	RENAMED_FROM_ATTRIBUTE, PURE_ATTRIBUTE,
+};
 
 use proc_macro2::{Span, TokenStream};
 
-use syn::{Ident, ItemTrait, Result};
+use syn::{Ident, ItemTrait, TraitItem, Result, Error};
+
+use std::collections::{HashMap, HashSet};
 
 use inflector::Inflector;
 
@@ -39,11 +45,20 @@ pub mod keywords {
 /// It expects the trait definition the attribute was put above and if this should be an wasm only
 /// interface.
 pub fn runtime_interface_impl(trait_def: ItemTrait, is_wasm_only: bool) -> Result<TokenStream> {
+	let (trait_def, versions) = process_versions(trait_def)?;
+	let (trait_def, aliases) = process_aliases(trait_def, &versions)?;
+	let (trait_def, pure_functions) = process_purity(trait_def)?;
 	let bare_functions = bare_function_interface::generate(&trait_def, is_wasm_only)?;
 	let crate_include = generate_runtime_interface_include();
 	let mod_name = Ident::new(&trait_def.ident.to_string().to_snake_case(), Span::call_site());
 	let trait_decl_impl = trait_decl_impl::process(&trait_def, is_wasm_only)?;
-	let host_functions = host_function_interface::generate(&trait_def, is_wasm_only)?;
+	let host_functions = host_function_interface::generate(
+		&trait_def,
+		&versions,
+		&aliases,
+		&pure_functions,
+		is_wasm_only,
+	)?;
 	let vis = trait_def.vis;
 	let attrs = &trait_def.attrs;
 
@@ -63,3 +78,113 @@ pub fn runtime_interface_impl(trait_def: ItemTrait, is_wasm_only: bool) -> Resul
 
 	Ok(res)
 }
+
+/// The original name and the version of each function, by the name of the function in the
+/// trait definition returned by [`process_versions`].
+pub type FunctionVersions = HashMap<Ident, (Ident, u32)>;
+
+/// The former names of each function, by the name of the function in the trait definition
+/// returned by [`process_aliases`].
+pub type FunctionAliases = HashMap<Ident, Vec<Ident>>;
+
+/// The names of the functions declared as pure, in the trait definition returned by
+/// [`process_purity`].
+pub type PureFunctions = HashSet<Ident>;
+
+/// Process the `#[version(N)]` attributes of all trait methods.
+///
+/// The attributes are removed and every method that is not the latest version of a function is
+/// renamed to `NAME_version_N`. So, the latest version is callable by the original name.
+fn process_versions(mut trait_def: ItemTrait) -> Result<(ItemTrait, FunctionVersions)> {
+	let mut latest = HashMap::<Ident, u32>::new();
+	let mut declared = HashSet::new();
+
+	for item in &trait_def.items {
+		if let TraitItem::Method(method) = item {
+			let version = get_function_version(&method.attrs)?;
+
+			if !declared.insert((method.sig.ident.to_string(), version)) {
+				return Err(
+					Error::new(
+						method.sig.ident.span(),
+						format!("Version `{}` of `{}` is declared twice.", version, method.sig.ident),
+					)
+				)
+			}
+
+			let latest = latest.entry(method.sig.ident.clone()).or_insert(version);
+			*latest = (*latest).max(version);
+		}
+	}
+
+	let mut versions = FunctionVersions::new();
+
+	for item in &mut trait_def.items {
+		if let TraitItem::Method(method) = item {
+			let version = get_function_version(&method.attrs)?;
+			method.attrs.retain(|a| !a.path.is_ident(VERSION_ATTRIBUTE));
+
+			let name = method.sig.ident.clone();
+			if version < latest[&name] {
+				method.sig.ident = create_function_ident_with_version(&name, version);
+			}
+
+			versions.insert(method.sig.ident.clone(), (name, version));
+		}
+	}
+
+	Ok((trait_def, versions))
+}
+
+/// Process the `#[renamed_from("NAME")]` attributes of all trait methods.
+///
+/// The attributes are removed. The host additionally provides each function under its former
+/// names, so wasm runtimes that import a function by a former name keep working.
+fn process_aliases(
+	mut trait_def: ItemTrait,
+	versions: &FunctionVersions,
+) -> Result<(ItemTrait, FunctionAliases)> {
+	let mut aliases = FunctionAliases::new();
+
+	for item in &mut trait_def.items {
+		if let TraitItem::Method(method) = item {
+			let old_names = get_function_old_names(&method.attrs)?;
+			method.attrs.retain(|a| !a.path.is_ident(RENAMED_FROM_ATTRIBUTE));
+
+			let (name, _) = &versions[&method.sig.ident];
+			if let Some(old_name) = old_names.iter().find(|n| *n == name) {
+				return Err(
+					Error::new(old_name.span(), "A function can not be renamed from its own name.")
+				)
+			}
+
+			if !old_names.is_empty() {
+				aliases.insert(method.sig.ident.clone(), old_names);
+			}
+		}
+	}
+
+	Ok((trait_def, aliases))
+}
+
+/// Process the `#[pure]` attributes of all trait methods.
+///
+/// The attributes are removed. The host functions of pure functions report this through
+/// `Function::is_pure`.
+fn process_purity(mut trait_def: ItemTrait) -> Result<(ItemTrait, PureFunctions)> {
+	let mut pure_functions = PureFunctions::new();
+
+	for item in &mut trait_def.items {
+		if let TraitItem::Method(method) = item {
+			let is_pure = get_function_is_pure(&method.attrs)?;
+			method.attrs.retain(|a| !a.path.is_ident(PURE_ATTRIBUTE));
+
+			if is_pure {
+				ensure_pure_signature(&method.sig)?;
+				pure_functions.insert(method.sig.ident.clone());
+			}
+		}
+	}
+
+	Ok((trait_def, pure_functions))
+}
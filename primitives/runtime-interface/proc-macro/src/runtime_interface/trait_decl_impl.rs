@@ -17,11 +17,14 @@
 //! Checks the trait declaration, makes the trait declaration module local, removes all method
 //! default implementations and implements the trait for `&mut dyn Externalities`.
 
-use crate::utils::{generate_crate_access, get_function_argument_types_without_ref};
+use crate::utils::{
+	generate_crate_access, get_function_argument_types_without_ref, ensure_no_by_value_self,
+	ensure_return_type_is_owned, ensure_snake_case_name, ensure_elided_argument_lifetimes,
+};
 
 use syn::{
 	ItemTrait, TraitItemMethod, Result, TraitItem, Error, fold::{self, Fold}, spanned::Spanned,
-	Visibility, Receiver, Type, Generics,
+	Visibility, Type, Generics,
 };
 
 use proc_macro2::TokenStream;
@@ -98,6 +101,22 @@ impl Fold for ToEssentialTraitDef {
 
 		self.error_on_generic_parameters(&method.sig.generics);
 
+		if let Err(err) = ensure_no_by_value_self(&method.sig) {
+			self.errors.push(err);
+		}
+
+		if let Err(err) = ensure_return_type_is_owned(&method.sig) {
+			self.errors.push(err);
+		}
+
+		if let Err(err) = ensure_elided_argument_lifetimes(&method.sig) {
+			self.errors.push(err);
+		}
+
+		if let Err(err) = ensure_snake_case_name(&method.sig) {
+			self.errors.push(err);
+		}
+
 		fold::fold_trait_item_method(self, method)
 	}
 
@@ -107,14 +126,6 @@ impl Fold for ToEssentialTraitDef {
 		trait_def.vis = Visibility::Inherited;
 		fold::fold_item_trait(self, trait_def)
 	}
-
-	fn fold_receiver(&mut self, receiver: Receiver) -> Receiver {
-		if receiver.reference.is_none() {
-			self.push_error(&receiver, "Taking `Self` by value is not allowed.");
-		}
-
-		fold::fold_receiver(self, receiver)
-	}
 }
 
 /// Implements the given trait definition for `dyn Externalities`.
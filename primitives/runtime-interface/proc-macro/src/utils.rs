@@ -20,7 +20,7 @@ use proc_macro2::{TokenStream, Span};
 
 use syn::{
 	Ident, Error, Signature, Pat, PatType, FnArg, Type, token, TraitItemMethod, ItemTrait,
-	TraitItem, parse_quote, spanned::Spanned,
+	TraitItem, Attribute, LitInt, LitStr, Result, ReturnType, parse_quote, spanned::Spanned,
 };
 
 use proc_macro_crate::crate_name;
@@ -31,33 +31,71 @@ use quote::quote;
 
 use inflector::Inflector;
 
+/// The name of the attribute that declares the version of a runtime interface function.
+pub const VERSION_ATTRIBUTE: &str = "version";
+
+/// The name of the attribute that declares a former name of a runtime interface function.
+pub const RENAMED_FROM_ATTRIBUTE: &str = "renamed_from";
+
+/// The name of the attribute that declares a runtime interface function as pure.
+pub const PURE_ATTRIBUTE: &str = "pure";
+
+/// The package name of the runtime-interface crate.
+const RUNTIME_INTERFACE_PACKAGE: &str = "sp-runtime-interface";
+
+/// The name the runtime-interface crate is imported as by [`generate_runtime_interface_include`].
+const RUNTIME_INTERFACE_ALIAS: &str = "proc_macro_runtime_interface";
+
 /// Generates the include for the runtime-interface crate.
 pub fn generate_runtime_interface_include() -> TokenStream {
-	if env::var("CARGO_PKG_NAME").unwrap() == "sp-runtime-interface" {
-		TokenStream::new()
-	} else {
-		match crate_name("sp-runtime-interface") {
-			Ok(crate_name) => {
-				let crate_name = Ident::new(&crate_name, Span::call_site());
-				quote!(
-					#[doc(hidden)]
-					extern crate #crate_name as proc_macro_runtime_interface;
-				)
-			},
-			Err(e) => {
-				let err = Error::new(Span::call_site(), &e).to_compile_error();
-				quote!( #err )
-			}
-		}
-	}
+	runtime_interface_include(
+		&env::var("CARGO_PKG_NAME").unwrap(),
+		|| crate_name(RUNTIME_INTERFACE_PACKAGE),
+	)
 }
 
 /// Generates the access to the `sp-runtime-interface` crate.
 pub fn generate_crate_access() -> TokenStream {
-	if env::var("CARGO_PKG_NAME").unwrap() == "sp-runtime-interface" {
-		quote!( sp_runtime_interface )
+	let crate_ = crate_access(&env::var("CARGO_PKG_NAME").unwrap());
+	quote!( #crate_ )
+}
+
+/// Generates the include for the runtime-interface crate, when compiling the package `pkg_name`.
+///
+/// `resolve_crate_name` returns the name of the runtime-interface dependency, which differs from
+/// the package name when the dependency is renamed. The crate is always imported using the
+/// same alias, so that [`crate_access`] does not need to know the resolved name.
+fn runtime_interface_include(
+	pkg_name: &str,
+	resolve_crate_name: impl FnOnce() -> std::result::Result<String, String>,
+) -> TokenStream {
+	if pkg_name == RUNTIME_INTERFACE_PACKAGE {
+		return TokenStream::new()
+	}
+
+	match resolve_crate_name() {
+		Ok(crate_name) => {
+			let crate_name = Ident::new(&crate_name, Span::call_site());
+			let crate_ = crate_access(pkg_name);
+			quote!(
+				#[doc(hidden)]
+				extern crate #crate_name as #crate_;
+			)
+		},
+		Err(e) => {
+			let err = Error::new(Span::call_site(), &e).to_compile_error();
+			quote!( #err )
+		}
+	}
+}
+
+/// Returns the identifier to access the runtime-interface crate, when compiling the package
+/// `pkg_name`.
+fn crate_access(pkg_name: &str) -> Ident {
+	if pkg_name == RUNTIME_INTERFACE_PACKAGE {
+		Ident::new("sp_runtime_interface", Span::call_site())
 	} else {
-		quote!( proc_macro_runtime_interface )
+		Ident::new(RUNTIME_INTERFACE_ALIAS, Span::call_site())
 	}
 }
 
@@ -66,18 +104,181 @@ pub fn create_exchangeable_host_function_ident(name: &Ident) -> Ident {
 	Ident::new(&format!("host_{}", name), Span::call_site())
 }
 
-/// Create the host function identifier for the given function name.
-pub fn create_host_function_ident(name: &Ident, trait_name: &Ident) -> Ident {
+/// Create the host function identifier for the given function name and version.
+pub fn create_host_function_ident(name: &Ident, version: u32, trait_name: &Ident) -> Ident {
 	Ident::new(
 		&format!(
-			"ext_{}_{}_version_1",
+			"ext_{}_{}_version_{}",
 			trait_name.to_string().to_snake_case(),
 			name,
+			version,
 		),
 		Span::call_site(),
 	)
 }
 
+/// Create the host function identifiers for the given function name and version, followed by the
+/// identifiers for each of its former names.
+pub fn create_host_function_alias_idents(
+	name: &Ident,
+	old_names: &[Ident],
+	version: u32,
+	trait_name: &Ident,
+) -> Vec<Ident> {
+	Some(name).into_iter()
+		.chain(old_names)
+		.map(|name| create_host_function_ident(name, version, trait_name))
+		.collect()
+}
+
+/// Create the function identifier for the given function name and version.
+///
+/// This is used for all functions that are not the latest version of a function.
+pub fn create_function_ident_with_version(name: &Ident, version: u32) -> Ident {
+	Ident::new(&format!("{}_version_{}", name, version), name.span())
+}
+
+/// Returns the version given by the `#[version(N)]` attribute or `1` if there is no such attribute.
+pub fn get_function_version(attrs: &[Attribute]) -> Result<u32> {
+	let mut version_attrs = attrs.iter().filter(|a| a.path.is_ident(VERSION_ATTRIBUTE));
+
+	let version = match version_attrs.next() {
+		Some(attr) => {
+			let version = attr.parse_args::<LitInt>()?;
+			let res = version.base10_parse::<u32>()?;
+
+			if res == 0 {
+				return Err(Error::new(version.span(), "Versions start at `1`."))
+			}
+
+			res
+		},
+		None => 1,
+	};
+
+	if let Some(duplicate) = version_attrs.next() {
+		return Err(Error::new(duplicate.span(), "Duplicated `version` attribute."))
+	}
+
+	Ok(version)
+}
+
+/// Returns the former names given by the `#[renamed_from("NAME")]` attributes.
+pub fn get_function_old_names(attrs: &[Attribute]) -> Result<Vec<Ident>> {
+	attrs.iter()
+		.filter(|a| a.path.is_ident(RENAMED_FROM_ATTRIBUTE))
+		.map(|attr| {
+			let old_name = attr.parse_args::<LitStr>()?;
+			let ident = old_name.parse::<Ident>()?;
+
+			if ident.to_string().chars().any(char::is_uppercase) {
+				return Err(Error::new(old_name.span(), "Former names need to be snake_case."))
+			}
+
+			Ok(ident)
+		})
+		.collect()
+}
+
+/// Returns if the function is declared as pure with the `#[pure]` attribute.
+pub fn get_function_is_pure(attrs: &[Attribute]) -> Result<bool> {
+	let mut pure_attrs = attrs.iter().filter(|a| a.path.is_ident(PURE_ATTRIBUTE));
+
+	let is_pure = match pure_attrs.next() {
+		Some(attr) if !attr.tokens.is_empty() => {
+			return Err(Error::new(attr.tokens.span(), "`pure` does not take any arguments."))
+		},
+		Some(_) => true,
+		None => false,
+	};
+
+	if let Some(duplicate) = pure_attrs.next() {
+		return Err(Error::new(duplicate.span(), "Duplicated `pure` attribute."))
+	}
+
+	Ok(is_pure)
+}
+
+/// Ensures that the given `Signature` can be declared as pure.
+///
+/// A pure function can neither access the externalities through `self` nor write into
+/// preallocated wasm memory through a `&mut` argument.
+pub fn ensure_pure_signature(sig: &Signature) -> Result<()> {
+	if let Some(receiver) = sig.receiver() {
+		return Err(Error::new(receiver.span(), "Pure functions can not access `self`."))
+	}
+
+	match get_mutable_reference_arguments(sig).next() {
+		Some((name, _)) => Err(
+			Error::new(name.span(), "Pure functions can not take `&mut` arguments.")
+		),
+		None => Ok(()),
+	}
+}
+
+/// Ensures that the given `Signature` does not take `self` by value.
+///
+/// The argument helpers below filter out the `self` argument, so this needs to be checked
+/// explicitly.
+pub fn ensure_no_by_value_self(sig: &Signature) -> Result<()> {
+	match sig.receiver() {
+		Some(FnArg::Receiver(receiver)) if receiver.reference.is_none() => Err(
+			Error::new(receiver.self_token.span(), "Taking `Self` by value is not allowed.")
+		),
+		_ => Ok(()),
+	}
+}
+
+/// Ensures that the given `Signature` does not return a reference.
+///
+/// The returned reference would point to data that is not valid anymore after the call returned.
+pub fn ensure_return_type_is_owned(sig: &Signature) -> Result<()> {
+	match &sig.output {
+		ReturnType::Type(_, ty) => match &**ty {
+			Type::Reference(reference) => Err(
+				Error::new(reference.span(), "Returning a reference is not allowed.")
+			),
+			_ => Ok(()),
+		},
+		ReturnType::Default => Ok(()),
+	}
+}
+
+/// Ensures that the reference arguments of the given `Signature` do not name a lifetime.
+///
+/// The referenced data is only valid for the duration of the call, so a named lifetime like
+/// `'static` would not hold on the host side.
+pub fn ensure_elided_argument_lifetimes(sig: &Signature) -> Result<()> {
+	let named = get_function_arguments(sig).find_map(|pt| match *pt.ty {
+		Type::Reference(type_ref) => type_ref.lifetime.filter(|lifetime| lifetime.ident != "_"),
+		_ => None,
+	});
+
+	match named {
+		Some(lifetime) => Err(
+			Error::new(lifetime.span(), "Reference arguments can not have a named lifetime.")
+		),
+		None => Ok(()),
+	}
+}
+
+/// Ensures that the name of the given `Signature` is snake_case.
+///
+/// The name is used as is in the exported host function symbol, while the trait name is converted
+/// to snake_case.
+pub fn ensure_snake_case_name(sig: &Signature) -> Result<()> {
+	let name = sig.ident.to_string();
+
+	if name.chars().any(char::is_uppercase) {
+		Err(Error::new(
+			sig.ident.span(),
+			format!("Function names need to be snake_case, use `{}`.", name.to_snake_case()),
+		))
+	} else {
+		Ok(())
+	}
+}
+
 /// Returns the function arguments of the given `Signature`, minus any `self` arguments.
 pub fn get_function_arguments<'a>(sig: &'a Signature) -> impl Iterator<Item = PatType> + 'a {
 	sig.inputs
@@ -102,6 +303,11 @@ pub fn get_function_arguments<'a>(sig: &'a Signature) -> impl Iterator<Item = Pa
 		})
 }
 
+/// Returns the number of function arguments of the given `Signature`, minus any `self` argument.
+pub fn function_argument_count(sig: &Signature) -> usize {
+	get_function_arguments(sig).count()
+}
+
 /// Returns the function argument names of the given `Signature`, minus any `self`.
 pub fn get_function_argument_names<'a>(sig: &'a Signature) -> impl Iterator<Item = Box<Pat>> + 'a {
 	get_function_arguments(sig).map(|pt| pt.pat)
@@ -150,6 +356,35 @@ pub fn get_function_argument_types_ref_and_mut<'a>(
 		})
 }
 
+/// Returns the names and the referenced types of all function arguments that are given as `&mut`,
+/// minus the `self` arg.
+pub fn get_mutable_reference_arguments<'a>(
+	sig: &'a Signature,
+) -> impl Iterator<Item = (Box<Pat>, Box<Type>)> + 'a {
+	get_function_arguments(sig)
+		.filter_map(|pt| match *pt.ty {
+			Type::Reference(type_ref) if type_ref.mutability.is_some() => Some((pt.pat, type_ref.elem)),
+			_ => None,
+		})
+}
+
+/// Returns if the given function argument type is `&[u8]`.
+///
+/// Such arguments are borrowed from the wasm memory by the host function implementation, instead
+/// of being copied.
+pub fn is_borrowed_byte_slice(ty: &Type) -> bool {
+	match ty {
+		Type::Reference(type_ref) if type_ref.mutability.is_none() => match &*type_ref.elem {
+			Type::Slice(slice) => match &*slice.elem {
+				Type::Path(path) => path.qself.is_none() && path.path.is_ident("u8"),
+				_ => false,
+			},
+			_ => false,
+		},
+		_ => false,
+	}
+}
+
 /// Returns an iterator over all trait methods for the given trait definition.
 pub fn get_trait_methods<'a>(trait_def: &'a ItemTrait) -> impl Iterator<Item = &'a TraitItemMethod> {
 	trait_def
@@ -160,3 +395,221 @@ pub fn get_trait_methods<'a>(trait_def: &'a ItemTrait) -> impl Iterator<Item = &
 			_ => None,
 		})
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn runtime_interface_include_works_with_renamed_crate() {
+		let include = runtime_interface_include("my-runtime", || Ok("renamed_interface".into()));
+		let crate_ = crate_access("my-runtime");
+
+		assert_eq!(
+			include.to_string(),
+			quote!(
+				#[doc(hidden)]
+				extern crate renamed_interface as #crate_;
+			).to_string(),
+		);
+		assert_eq!(crate_.to_string(), "proc_macro_runtime_interface");
+	}
+
+	#[test]
+	fn runtime_interface_include_works_in_runtime_interface_crate() {
+		let include = runtime_interface_include(
+			"sp-runtime-interface",
+			|| panic!("The crate name is not required"),
+		);
+
+		assert!(include.is_empty());
+		assert_eq!(crate_access("sp-runtime-interface").to_string(), "sp_runtime_interface");
+	}
+
+	#[test]
+	fn create_host_function_ident_works() {
+		let trait_name = Ident::new("TestApi", Span::call_site());
+		let name = Ident::new("return_input", Span::call_site());
+
+		assert_eq!(
+			create_host_function_ident(&name, 1, &trait_name).to_string(),
+			"ext_test_api_return_input_version_1",
+		);
+		assert_eq!(
+			create_host_function_ident(&name, 2, &trait_name).to_string(),
+			"ext_test_api_return_input_version_2",
+		);
+	}
+
+	#[test]
+	fn create_host_function_alias_idents_works() {
+		let name = Ident::new("new_name", Span::call_site());
+		let old_names = vec![
+			Ident::new("old_name", Span::call_site()),
+			Ident::new("older_name", Span::call_site()),
+		];
+		let trait_name = Ident::new("MyTrait", Span::call_site());
+
+		let idents = create_host_function_alias_idents(&name, &old_names, 2, &trait_name)
+			.into_iter()
+			.map(|ident| ident.to_string())
+			.collect::<Vec<_>>();
+		assert_eq!(
+			idents,
+			vec![
+				"ext_my_trait_new_name_version_2",
+				"ext_my_trait_old_name_version_2",
+				"ext_my_trait_older_name_version_2",
+			],
+		);
+		assert_eq!(create_host_function_alias_idents(&name, &[], 1, &trait_name).len(), 1);
+	}
+
+	#[test]
+	fn get_function_old_names_works() {
+		let method: TraitItemMethod = parse_quote!( fn test(); );
+		assert!(get_function_old_names(&method.attrs).unwrap().is_empty());
+
+		let method: TraitItemMethod = parse_quote!(
+			#[renamed_from("old")] #[version(2)] #[renamed_from("older")] fn test();
+		);
+		let old_names = get_function_old_names(&method.attrs).unwrap();
+		assert_eq!(old_names, vec!["old", "older"]);
+
+		let method: TraitItemMethod = parse_quote!( #[renamed_from("not an ident")] fn test(); );
+		assert!(get_function_old_names(&method.attrs).is_err());
+		let method: TraitItemMethod = parse_quote!( #[renamed_from("oldName")] fn test(); );
+		assert!(get_function_old_names(&method.attrs).is_err());
+	}
+
+	#[test]
+	fn get_function_version_works() {
+		let method: TraitItemMethod = parse_quote!( fn test(); );
+		assert_eq!(get_function_version(&method.attrs).unwrap(), 1);
+
+		let method: TraitItemMethod = parse_quote!( #[version(2)] fn test(); );
+		assert_eq!(get_function_version(&method.attrs).unwrap(), 2);
+
+		let method: TraitItemMethod = parse_quote!( #[version(0)] fn test(); );
+		assert!(get_function_version(&method.attrs).is_err());
+
+		let method: TraitItemMethod = parse_quote!( #[version(2)] #[version(3)] fn test(); );
+		assert!(get_function_version(&method.attrs).is_err());
+	}
+
+	#[test]
+	fn get_function_is_pure_works() {
+		let method: TraitItemMethod = parse_quote!( fn test(); );
+		assert!(!get_function_is_pure(&method.attrs).unwrap());
+
+		let method: TraitItemMethod = parse_quote!( #[version(2)] #[pure] fn test(); );
+		assert!(get_function_is_pure(&method.attrs).unwrap());
+
+		let method: TraitItemMethod = parse_quote!( #[pure(true)] fn test(); );
+		assert!(get_function_is_pure(&method.attrs).is_err());
+
+		let method: TraitItemMethod = parse_quote!( #[pure] #[pure] fn test(); );
+		assert!(get_function_is_pure(&method.attrs).is_err());
+	}
+
+	#[test]
+	fn is_borrowed_byte_slice_works() {
+		assert!(is_borrowed_byte_slice(&parse_quote!( &[u8] )));
+
+		assert!(!is_borrowed_byte_slice(&parse_quote!( &mut [u8] )));
+		assert!(!is_borrowed_byte_slice(&parse_quote!( &[u32] )));
+		assert!(!is_borrowed_byte_slice(&parse_quote!( &[u8; 32] )));
+		assert!(!is_borrowed_byte_slice(&parse_quote!( &Vec<u8> )));
+		assert!(!is_borrowed_byte_slice(&parse_quote!( Vec<u8> )));
+	}
+
+	#[test]
+	fn ensure_pure_signature_works() {
+		let ensure = |method: TraitItemMethod| ensure_pure_signature(&method.sig);
+
+		assert!(ensure(parse_quote!( fn test(data: &[u8]) -> [u8; 32]; )).is_ok());
+
+		let err = ensure(parse_quote!( fn test(&self, data: u32); )).unwrap_err();
+		assert_eq!(err.to_string(), "Pure functions can not access `self`.");
+		let err = ensure(parse_quote!( fn test(data: &mut [u8]); )).unwrap_err();
+		assert_eq!(err.to_string(), "Pure functions can not take `&mut` arguments.");
+	}
+
+	#[test]
+	fn get_mutable_reference_arguments_works() {
+		let method: TraitItemMethod = parse_quote!(
+			fn test(&mut self, a: &[u8], b: &mut [u8], c: u32, d: &mut [u8; 32]);
+		);
+		let args = get_mutable_reference_arguments(&method.sig)
+			.map(|(pat, ty)| (quote!(#pat).to_string(), quote!(#ty).to_string()))
+			.collect::<Vec<_>>();
+
+		assert_eq!(
+			args,
+			vec![
+				("b".to_string(), quote!([u8]).to_string()),
+				("d".to_string(), quote!([u8; 32]).to_string()),
+			],
+		);
+	}
+
+	#[test]
+	fn function_argument_count_works() {
+		let method: TraitItemMethod = parse_quote!( fn test(&self, a: u32, _: &[u8]); );
+		assert_eq!(function_argument_count(&method.sig), 2);
+
+		let method: TraitItemMethod = parse_quote!( fn test(&mut self); );
+		assert_eq!(function_argument_count(&method.sig), 0);
+	}
+
+	#[test]
+	fn ensure_no_by_value_self_works() {
+		let ensure = |method: TraitItemMethod| ensure_no_by_value_self(&method.sig);
+
+		assert!(ensure(parse_quote!( fn test(&self, data: u32); )).is_ok());
+		assert!(ensure(parse_quote!( fn test(&mut self); )).is_ok());
+		assert!(ensure(parse_quote!( fn test(data: u32); )).is_ok());
+
+		let err = ensure(parse_quote!( fn test(self, data: u32); )).unwrap_err();
+		assert_eq!(err.to_string(), "Taking `Self` by value is not allowed.");
+	}
+
+	#[test]
+	fn ensure_elided_argument_lifetimes_works() {
+		let ensure = |method: TraitItemMethod| ensure_elided_argument_lifetimes(&method.sig);
+
+		assert!(ensure(parse_quote!( fn test(&self, a: &[u8], b: &mut [u8; 32]); )).is_ok());
+		assert!(ensure(parse_quote!( fn test(data: &'_ [u8]); )).is_ok());
+
+		let err = ensure(parse_quote!( fn test(data: &'a [u8]); )).unwrap_err();
+		assert_eq!(err.to_string(), "Reference arguments can not have a named lifetime.");
+		let err = ensure(parse_quote!( fn test(a: u32, b: &'static mut [u8]); )).unwrap_err();
+		assert_eq!(err.to_string(), "Reference arguments can not have a named lifetime.");
+	}
+
+	#[test]
+	fn ensure_snake_case_name_works() {
+		let ensure = |method: TraitItemMethod| ensure_snake_case_name(&method.sig);
+
+		assert!(ensure(parse_quote!( fn my_func(); )).is_ok());
+		assert!(ensure(parse_quote!( fn return_u16_array(); )).is_ok());
+
+		let err = ensure(parse_quote!( fn myFunc(); )).unwrap_err();
+		assert_eq!(err.to_string(), "Function names need to be snake_case, use `my_func`.");
+		let err = ensure(parse_quote!( fn MyFunc(); )).unwrap_err();
+		assert_eq!(err.to_string(), "Function names need to be snake_case, use `my_func`.");
+	}
+
+	#[test]
+	fn ensure_return_type_is_owned_works() {
+		let ensure = |method: TraitItemMethod| ensure_return_type_is_owned(&method.sig);
+
+		assert!(ensure(parse_quote!( fn test(&self) -> Vec<u8>; )).is_ok());
+		assert!(ensure(parse_quote!( fn test(data: &[u8]); )).is_ok());
+
+		let err = ensure(parse_quote!( fn test(&self) -> &[u8]; )).unwrap_err();
+		assert_eq!(err.to_string(), "Returning a reference is not allowed.");
+		let err = ensure(parse_quote!( fn test() -> &'static str; )).unwrap_err();
+		assert_eq!(err.to_string(), "Returning a reference is not allowed.");
+	}
+}
@@ -0,0 +1,251 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Provides [`CompactOption`], an `Option` of a small primitive that is passed without SCALE
+//! encoding.
+//!
+//! `Option<T>` itself is always passed SCALE encoded, see [`compact_result`](crate::compact_result)
+//! for why the compact representation needs a wrapper.
+//!
+//! A `u64` leaves no room for the presence bit, so `CompactOption<u64>` is passed as a nullable
+//! pointer instead, like a [`NullableArray`](crate::nullable::NullableArray).
+
+use crate::{RIType, compact_result::CompactValue};
+
+#[cfg(feature = "std")]
+use crate::host::*;
+#[cfg(not(feature = "std"))]
+use crate::wasm::*;
+
+#[cfg(feature = "std")]
+use sp_wasm_interface::{FunctionContext, Pointer};
+
+/// The bit of the ffi value that is set for `Some`.
+const SOME_FLAG: u64 = 1 << 32;
+
+/// An `Option` that is passed as a single `u64`, without SCALE encoding and without allocating.
+///
+/// `None` is passed as `0`. For `Some`, the lower 32 bits hold the [`CompactValue`]
+/// representation of the value and bit 32 is set.
+///
+/// `CompactOption<u64>` is passed as a single `u32` instead. `None` is passed as the pointer `0`
+/// and does not allocate. `Some` is passed as the pointer to the 8 little-endian bytes of the
+/// value, which the host allocates when it returns one.
+///
+/// # Example
+///
+/// ```
+/// #[sp_runtime_interface::runtime_interface]
+/// trait Interface {
+///     fn find(value: u32) -> sp_runtime_interface::compact_option::CompactOption<u32> {
+///         if value > 10 { Some(value - 10) } else { None }.into()
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CompactOption<T>(pub Option<T>);
+
+impl<T> From<Option<T>> for CompactOption<T> {
+	fn from(option: Option<T>) -> Self {
+		CompactOption(option)
+	}
+}
+
+impl<T> From<CompactOption<T>> for Option<T> {
+	fn from(option: CompactOption<T>) -> Self {
+		option.0
+	}
+}
+
+impl<T: CompactValue> CompactOption<T> {
+	/// Pack `self` into its ffi value.
+	fn pack(self) -> u64 {
+		match self.0 {
+			Some(value) => SOME_FLAG | value.into_compact() as u64,
+			None => 0,
+		}
+	}
+
+	/// Unpack the ffi value, returns `None` if `value` does not represent any `Self`.
+	fn unpack(value: u64) -> Option<Self> {
+		let option = match value >> 32 {
+			0 if value == 0 => None,
+			1 => Some(T::from_compact(value as u32)?),
+			_ => return None,
+		};
+
+		Some(CompactOption(option))
+	}
+}
+
+impl<T: CompactValue> RIType for CompactOption<T> {
+	type FFIType = u64;
+}
+
+#[cfg(not(feature = "std"))]
+impl<T: CompactValue> IntoFFIValue for CompactOption<T> {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64> {
+		self.pack().into()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl<T: CompactValue> FromFFIValue for CompactOption<T> {
+	fn from_ffi_value(arg: u64) -> Self {
+		Self::unpack(arg).expect("The host only passes valid compact options; qed")
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: CompactValue> FromFFIValue for CompactOption<T> {
+	type SelfInstance = Self;
+
+	fn from_ffi_value(_: &mut dyn FunctionContext, arg: u64) -> Result<Self> {
+		Self::unpack(arg)
+			.ok_or_else(|| format!("Invalid compact option provided: {:#x}", arg).into())
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: CompactValue> IntoFFIValue for CompactOption<T> {
+	fn into_ffi_value(self, _: &mut dyn FunctionContext) -> Result<u64> {
+		Ok(self.pack())
+	}
+}
+
+impl RIType for CompactOption<u64> {
+	type FFIType = u32;
+}
+
+#[cfg(not(feature = "std"))]
+impl IntoFFIValue for CompactOption<u64> {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u32> {
+		match &self.0 {
+			// wasm is little-endian, so the value is laid out as its little-endian bytes.
+			Some(value) => (value as *const u64 as u32).into(),
+			None => 0.into(),
+		}
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for CompactOption<u64> {
+	fn from_ffi_value(arg: u32) -> Self {
+		CompactOption(if arg == 0 {
+			None
+		} else {
+			Some(u64::from_le_bytes(<[u8; 8]>::from_ffi_value(arg)))
+		})
+	}
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for CompactOption<u64> {
+	type SelfInstance = Self;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u32) -> Result<Self> {
+		if arg == 0 {
+			return Ok(CompactOption(None))
+		}
+
+		let mut value = [0u8; 8];
+		context.read_memory_into(Pointer::new(arg), &mut value)?;
+		Ok(CompactOption(Some(u64::from_le_bytes(value))))
+	}
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for CompactOption<u64> {
+	fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u32> {
+		match self.0 {
+			Some(value) => crate::nullable::non_null(value.to_le_bytes().into_ffi_value(context)?),
+			None => Ok(0),
+		}
+	}
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pack_and_unpack_should_work() {
+		type O = CompactOption<u32>;
+
+		assert_eq!(O::from(None).pack(), 0);
+		assert_eq!(O::from(Some(0)).pack(), SOME_FLAG);
+		assert_eq!(O::from(Some(u32::max_value())).pack(), SOME_FLAG | 0xffff_ffff);
+		assert_eq!(O::unpack(0), Some(None.into()));
+		assert_eq!(O::unpack(SOME_FLAG), Some(Some(0).into()));
+
+		let signed = CompactOption::<i16>::from(Some(-2));
+		assert_eq!(CompactOption::unpack(signed.pack()), Some(signed));
+	}
+
+	#[test]
+	fn ffi_round_trip_should_not_allocate() {
+		// The context panics on any memory allocation or access.
+		let mut context = crate::impls::tests::TestContext::new(Vec::new());
+
+		for value in &[None, Some(0), Some(u32::max_value())] {
+			let value = CompactOption::from(*value);
+			let ffi_value = value.into_ffi_value(&mut context).unwrap();
+			assert_eq!(CompactOption::<u32>::from_ffi_value(&mut context, ffi_value).unwrap(), value);
+		}
+	}
+
+	#[test]
+	fn u64_should_round_trip_through_a_nullable_pointer() {
+		// The context panics on any memory allocation or access.
+		let mut context = crate::impls::tests::TestContext::new(Vec::new());
+		let none = CompactOption::<u64>(None).into_ffi_value(&mut context).unwrap();
+		assert_eq!(none, 0);
+		assert_eq!(CompactOption::<u64>::from_ffi_value(&mut context, 0), Ok(None.into()));
+
+		let mut context = crate::host::tests::CountingContext {
+			memory: vec![0],
+			..Default::default()
+		};
+		for value in &[0, 1 << 32, u64::max_value()] {
+			let ptr = CompactOption(Some(*value)).into_ffi_value(&mut context).unwrap();
+			assert_eq!(&context.memory[ptr as usize..][..8], &value.to_le_bytes()[..]);
+
+			let read = CompactOption::<u64>::from_ffi_value(&mut context, ptr).unwrap();
+			assert_eq!(read, Some(*value).into());
+		}
+		assert_eq!(context.allocations, 3);
+	}
+
+	#[test]
+	fn u64_allocated_at_the_null_pointer_should_error() {
+		let mut context = crate::host::tests::CountingContext::default();
+
+		assert!(CompactOption(Some(7u64)).into_ffi_value(&mut context).is_err());
+	}
+
+	#[test]
+	fn unpack_should_reject_invalid_values() {
+		type O = CompactOption<u8>;
+
+		assert_eq!(O::unpack(1), None);
+		assert_eq!(O::unpack(SOME_FLAG | 256), None);
+		assert_eq!(O::unpack(2 << 32), None);
+	}
+}
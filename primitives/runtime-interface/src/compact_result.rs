@@ -0,0 +1,239 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Provides [`CompactResult`], a `Result` of small primitives that is passed without SCALE
+//! encoding.
+//!
+//! `Result<T, E>` itself is always passed SCALE encoded. A dedicated representation for some `T`
+//! and `E` would overlap with this generic implementation, so the compact representation is
+//! opted into by using the [`CompactResult`] wrapper in the function signature instead.
+
+use crate::RIType;
+
+#[cfg(feature = "std")]
+use crate::host::*;
+#[cfg(not(feature = "std"))]
+use crate::wasm::*;
+
+#[cfg(feature = "std")]
+use sp_wasm_interface::FunctionContext;
+
+/// The bit of the ffi value that is set for `Err`.
+const ERR_FLAG: u64 = 1 << 32;
+
+/// A value that fits into 32 bits and can be used as `T` or `E` of a [`CompactResult`] and as `T`
+/// of a [`CompactOption`](crate::compact_option::CompactOption).
+pub trait CompactValue: Copy {
+	/// Convert `self` into its 32 bit representation.
+	fn into_compact(self) -> u32;
+
+	/// Convert the 32 bit representation back into `Self`.
+	///
+	/// Returns `None` if `value` is not the representation of any `Self`.
+	fn from_compact(value: u32) -> Option<Self>;
+}
+
+impl CompactValue for () {
+	fn into_compact(self) -> u32 {
+		0
+	}
+
+	fn from_compact(value: u32) -> Option<()> {
+		if value == 0 { Some(()) } else { None }
+	}
+}
+
+impl CompactValue for bool {
+	fn into_compact(self) -> u32 {
+		self as u32
+	}
+
+	fn from_compact(value: u32) -> Option<bool> {
+		match value {
+			0 => Some(false),
+			1 => Some(true),
+			_ => None,
+		}
+	}
+}
+
+/// Implement `CompactValue` for the given unsigned integers.
+macro_rules! impl_compact_value_for_unsigned {
+	( $( $ty:ty ),* $(,)? ) => {
+		$(
+			impl CompactValue for $ty {
+				fn into_compact(self) -> u32 {
+					self as u32
+				}
+
+				fn from_compact(value: u32) -> Option<$ty> {
+					use sp_std::convert::TryFrom;
+					<$ty>::try_from(value).ok()
+				}
+			}
+		)*
+	}
+}
+
+impl_compact_value_for_unsigned!(u8, u16, u32);
+
+/// Implement `CompactValue` for the given signed integers, which are passed as their bit pattern.
+macro_rules! impl_compact_value_for_signed {
+	( $( $ty:ty, $uty:ty ),* $(,)? ) => {
+		$(
+			impl CompactValue for $ty {
+				fn into_compact(self) -> u32 {
+					self as $uty as u32
+				}
+
+				fn from_compact(value: u32) -> Option<$ty> {
+					<$uty>::from_compact(value).map(|v| v as $ty)
+				}
+			}
+		)*
+	}
+}
+
+impl_compact_value_for_signed!(i8, u8, i16, u16, i32, u32);
+
+/// A `Result` that is passed as a single `u64`, without SCALE encoding and without allocating.
+///
+/// The lower 32 bits hold the [`CompactValue`] representation of the `Ok` or `Err` value, bit 32
+/// is set for `Err`.
+///
+/// # Example
+///
+/// ```
+/// #[sp_runtime_interface::runtime_interface]
+/// trait Interface {
+///     fn check(value: u32) -> sp_runtime_interface::compact_result::CompactResult<(), u8> {
+///         let result = if value > 10 { Err(3) } else { Ok(()) };
+///         result.into()
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CompactResult<T, E>(pub sp_std::result::Result<T, E>);
+
+impl<T, E> From<sp_std::result::Result<T, E>> for CompactResult<T, E> {
+	fn from(result: sp_std::result::Result<T, E>) -> Self {
+		CompactResult(result)
+	}
+}
+
+impl<T, E> From<CompactResult<T, E>> for sp_std::result::Result<T, E> {
+	fn from(result: CompactResult<T, E>) -> Self {
+		result.0
+	}
+}
+
+impl<T: CompactValue, E: CompactValue> CompactResult<T, E> {
+	/// Pack `self` into its ffi value.
+	fn pack(self) -> u64 {
+		match self.0 {
+			Ok(ok) => ok.into_compact() as u64,
+			Err(err) => ERR_FLAG | err.into_compact() as u64,
+		}
+	}
+
+	/// Unpack the ffi value, returns `None` if `value` does not represent any `Self`.
+	fn unpack(value: u64) -> Option<Self> {
+		let payload = value as u32;
+		let result = match value >> 32 {
+			0 => Ok(T::from_compact(payload)?),
+			1 => Err(E::from_compact(payload)?),
+			_ => return None,
+		};
+
+		Some(CompactResult(result))
+	}
+}
+
+impl<T: CompactValue, E: CompactValue> RIType for CompactResult<T, E> {
+	type FFIType = u64;
+}
+
+#[cfg(not(feature = "std"))]
+impl<T: CompactValue, E: CompactValue> IntoFFIValue for CompactResult<T, E> {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64> {
+		self.pack().into()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl<T: CompactValue, E: CompactValue> FromFFIValue for CompactResult<T, E> {
+	fn from_ffi_value(arg: u64) -> Self {
+		Self::unpack(arg).expect("The host only passes valid compact results; qed")
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: CompactValue, E: CompactValue> FromFFIValue for CompactResult<T, E> {
+	type SelfInstance = Self;
+
+	fn from_ffi_value(_: &mut dyn FunctionContext, arg: u64) -> Result<Self> {
+		Self::unpack(arg)
+			.ok_or_else(|| format!("Invalid compact result provided: {:#x}", arg).into())
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: CompactValue, E: CompactValue> IntoFFIValue for CompactResult<T, E> {
+	fn into_ffi_value(self, _: &mut dyn FunctionContext) -> Result<u64> {
+		Ok(self.pack())
+	}
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pack_and_unpack_should_work() {
+		type R = CompactResult<(), u8>;
+
+		assert_eq!(R::from(Ok(())).pack(), 0);
+		assert_eq!(R::from(Err(3)).pack(), ERR_FLAG | 3);
+		assert_eq!(R::unpack(0), Some(Ok(()).into()));
+		assert_eq!(R::unpack(ERR_FLAG | 3), Some(Err(3).into()));
+
+		let signed = CompactResult::<i16, bool>::from(Ok(-2));
+		assert_eq!(CompactResult::unpack(signed.pack()), Some(signed));
+	}
+
+	#[test]
+	fn ffi_round_trip_should_not_allocate() {
+		// The context panics on any memory allocation or access.
+		let mut context = crate::impls::tests::TestContext::new(Vec::new());
+
+		for value in &[Ok(()), Err(3)] {
+			let value = CompactResult::<(), u8>::from(*value);
+			let ffi_value = value.into_ffi_value(&mut context).unwrap();
+			assert_eq!(CompactResult::from_ffi_value(&mut context, ffi_value).unwrap(), value);
+		}
+	}
+
+	#[test]
+	fn unpack_should_reject_invalid_values() {
+		type R = CompactResult<(), u8>;
+
+		assert_eq!(R::unpack(1), None);
+		assert_eq!(R::unpack(ERR_FLAG | 256), None);
+		assert_eq!(R::unpack(2 << 32), None);
+	}
+}
@@ -18,7 +18,31 @@
 
 use crate::RIType;
 
-use sp_wasm_interface::{FunctionContext, Result};
+use sp_wasm_interface::FunctionContext;
+
+use std::borrow::Cow;
+
+/// The error of the host side conversions.
+///
+/// Fixed messages are borrowed and do not need to be allocated, formatted messages are owned.
+pub type Error = Cow<'static, str>;
+
+/// The result of the host side conversions.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Call `f`, which converts `what` from its ffi value, and turn a panic into an error.
+///
+/// The generated host functions convert every argument through this, so that a panicking
+/// [`FromFFIValue`] implementation does not unwind into the executor.
+#[doc(hidden)]
+pub fn catch_ffi_value_panic<R>(what: &str, f: impl FnOnce() -> Result<R>) -> Result<R> {
+	std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or_else(|panic| {
+		let message = panic.downcast_ref::<&str>().copied()
+			.or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+			.unwrap_or("Panicked without any further information!");
+		Err(format!("{} panicked: {}", what, message).into())
+	})
+}
 
 /// Something that can be converted into a ffi value.
 pub trait IntoFFIValue: RIType {
@@ -60,3 +84,81 @@ pub trait FromFFIValue: RIType {
 		arg: Self::FFIType,
 	) -> Result<Self::SelfInstance>;
 }
+
+/// Something that can be borrowed from a ffi value.
+///
+/// In contrast to [`FromFFIValue`], the value is not copied out of the wasm memory when the
+/// executor supports to borrow the memory directly. The generated host functions use this for
+/// arguments given as `&[u8]`, like the data of the hashing functions.
+pub trait WithFFIValue: RIType {
+	/// Call `f` with the value referenced by the given ffi value.
+	fn with_ffi_value<R>(
+		context: &dyn FunctionContext,
+		arg: Self::FFIType,
+		f: impl FnOnce(&Self) -> R,
+	) -> Result<R>;
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use sp_wasm_interface::{Pointer, Result, Sandbox, WordSize};
+
+	/// A `FunctionContext` with a bump allocator that counts the allocations and the writes.
+	#[derive(Default)]
+	pub(crate) struct CountingContext {
+		pub(crate) memory: Vec<u8>,
+		pub(crate) allocations: usize,
+		pub(crate) deallocations: usize,
+		pub(crate) writes: usize,
+	}
+
+	impl FunctionContext for CountingContext {
+		fn read_memory_into(&self, address: Pointer<u8>, dest: &mut [u8]) -> Result<()> {
+			let start = u32::from(address) as usize;
+			dest.copy_from_slice(&self.memory[start..start + dest.len()]);
+			Ok(())
+		}
+
+		fn memory_size(&self) -> WordSize {
+			self.memory.len() as WordSize
+		}
+
+		fn write_memory(&mut self, address: Pointer<u8>, data: &[u8]) -> Result<()> {
+			let start = u32::from(address) as usize;
+			self.memory[start..start + data.len()].copy_from_slice(data);
+			self.writes += 1;
+			Ok(())
+		}
+
+		fn allocate_memory(&mut self, size: WordSize) -> Result<Pointer<u8>> {
+			let ptr = Pointer::new(self.memory.len() as u32);
+			self.memory.resize(self.memory.len() + size as usize, 0);
+			self.allocations += 1;
+			Ok(ptr)
+		}
+
+		fn deallocate_memory(&mut self, _: Pointer<u8>) -> Result<()> {
+			self.deallocations += 1;
+			Ok(())
+		}
+
+		fn sandbox(&mut self) -> &mut dyn Sandbox {
+			unimplemented!()
+		}
+	}
+
+	#[test]
+	fn every_returned_vec_should_be_one_allocation() {
+		let mut context = CountingContext::default();
+		for i in 0..10_000u32 {
+			let data = i.to_le_bytes().to_vec();
+			let ffi_value = data.clone().into_ffi_value(&mut context).unwrap();
+			let (ptr, len) = crate::unpack_ptr_and_len(ffi_value);
+			assert_eq!(context.read_memory(Pointer::new(ptr), len).unwrap(), data);
+		}
+
+		// the runtime owns and frees the returned memory, so none of it can be reused.
+		assert_eq!((context.allocations, context.deallocations), (10_000, 0));
+	}
+}
@@ -29,18 +29,26 @@ use crate::wasm::*;
 use static_assertions::assert_eq_size;
 
 #[cfg(feature = "std")]
-use sp_wasm_interface::{FunctionContext, Result};
+use sp_wasm_interface::FunctionContext;
 
-use codec::{Encode, Decode};
+use codec::{Encode, Decode, Compact};
 
-use sp_std::{any::TypeId, mem, vec::Vec};
+use sp_std::{
+	any::TypeId, mem, vec::Vec, num::{NonZeroU16, NonZeroU32, NonZeroU64}, marker::PhantomData,
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet}, borrow::Cow,
+};
+
+use core::{time::Duration, ops::{Range, RangeInclusive}};
 
 #[cfg(feature = "std")]
-use sp_std::borrow::Cow;
+use crate::util::{write_encoded, checked_pointer_and_len, read_memory_small};
 
 #[cfg(not(feature = "std"))]
 use sp_std::{slice, boxed::Box};
 
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
 // Make sure that our assumptions for storing a pointer + its size in `u64` is valid.
 #[cfg(all(not(feature = "std"), not(feature = "disable_target_static_assertions")))]
 assert_eq_size!(usize, u32);
@@ -106,6 +114,152 @@ impl_traits_for_primitives! {
 	i64, i64,
 }
 
+/// Implement the traits for the given 128-bit primitive types.
+macro_rules! impl_traits_for_wide_primitives {
+	(
+		$(
+			$rty:ty
+		),*
+		$(,)?
+	) => {
+		$(
+			/// The type is passed as `u64`.
+			///
+			/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
+			///
+			/// The pointer points to the 16 little-endian bytes of `Self`, so the lower 64 bits
+			/// come first. Signed values are passed in two's complement.
+			///
+			/// There is no representation as two `u64` halves. Every argument and every return
+			/// value is passed as exactly one wasm value. The representation is also part of the
+			/// interface between the host and already built runtimes, so it can not differ with
+			/// the features the host is compiled with.
+			impl RIType for $rty {
+				type FFIType = u64;
+			}
+
+			#[cfg(not(feature = "std"))]
+			impl IntoFFIValue for $rty {
+				type Owned = Vec<u8>;
+
+				fn into_ffi_value(&self) -> WrappedFFIValue<u64, Vec<u8>> {
+					let data = self.to_le_bytes().to_vec();
+					let ffi_value = pack_ptr_and_len(data.as_ptr() as u32, data.len() as u32);
+					(ffi_value, data).into()
+				}
+			}
+
+			#[cfg(not(feature = "std"))]
+			impl FromFFIValue for $rty {
+				fn from_ffi_value(arg: u64) -> $rty {
+					let (ptr, len) = unpack_ptr_and_len(arg);
+					let len = len as usize;
+
+					// Make sure we free the pointer.
+					let data = unsafe { Vec::from_raw_parts(ptr as *mut u8, len, len) };
+					let mut res = [0u8; mem::size_of::<$rty>()];
+					res.copy_from_slice(&data);
+					<$rty>::from_le_bytes(res)
+				}
+			}
+
+			#[cfg(feature = "std")]
+			impl FromFFIValue for $rty {
+				type SelfInstance = $rty;
+
+				fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<$rty> {
+					let (ptr, len) = unpack_ptr_and_len(arg);
+
+					if len as usize != mem::size_of::<$rty>() {
+						return Err(
+							format!(
+								"Invalid length for `{}` (given {} vs expected {})!",
+								stringify!($rty),
+								len,
+								mem::size_of::<$rty>(),
+							).into()
+						)
+					}
+
+					let mut res = [0u8; mem::size_of::<$rty>()];
+					context.read_memory_into(Pointer::new(ptr), &mut res)?;
+					Ok(<$rty>::from_le_bytes(res))
+				}
+			}
+
+			#[cfg(feature = "std")]
+			impl IntoFFIValue for $rty {
+				fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u64> {
+					let data = self.to_le_bytes();
+
+					let ptr = context.allocate_memory(data.len() as u32)?;
+					context.write_memory(ptr, &data)?;
+
+					Ok(pack_ptr_and_len(ptr.into(), data.len() as u32))
+				}
+			}
+		)*
+	}
+}
+
+impl_traits_for_wide_primitives! {
+	u128,
+	i128,
+}
+
+/// Implement the traits for the given floating point types.
+macro_rules! impl_traits_for_floats {
+	(
+		$(
+			$rty:ty, $fty:ty,
+		)*
+	) => {
+		$(
+			/// The type is passed as its bit pattern, see `to_bits`.
+			impl RIType for $rty {
+				type FFIType = $fty;
+			}
+
+			#[cfg(not(feature = "std"))]
+			impl IntoFFIValue for $rty {
+				type Owned = ();
+
+				fn into_ffi_value(&self) -> WrappedFFIValue<$fty> {
+					self.to_bits().into()
+				}
+			}
+
+			#[cfg(not(feature = "std"))]
+			impl FromFFIValue for $rty {
+				fn from_ffi_value(arg: $fty) -> $rty {
+					<$rty>::from_bits(arg)
+				}
+			}
+
+			#[cfg(feature = "std")]
+			impl FromFFIValue for $rty {
+				type SelfInstance = $rty;
+
+				fn from_ffi_value(_: &mut dyn FunctionContext, arg: $fty) -> Result<$rty> {
+					Ok(<$rty>::from_bits(arg))
+				}
+			}
+
+			#[cfg(feature = "std")]
+			impl IntoFFIValue for $rty {
+				fn into_ffi_value(self, _: &mut dyn FunctionContext) -> Result<$fty> {
+					Ok(self.to_bits())
+				}
+			}
+		)*
+	}
+}
+
+impl_traits_for_floats! {
+	f32, u32,
+	f64, u64,
+}
+
 /// `bool` is passed as `u8`.
 ///
 /// - `1`: true
@@ -146,12 +300,339 @@ impl IntoFFIValue for bool {
 	}
 }
 
+/// `char` is passed as `u32`, the unicode scalar value.
+impl RIType for char {
+	type FFIType = u32;
+}
+
+#[cfg(not(feature = "std"))]
+impl IntoFFIValue for char {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u32> {
+		(*self as u32).into()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for char {
+	fn from_ffi_value(arg: u32) -> char {
+		// The host only passes valid `char`s to wasm, see the host side `IntoFFIValue`.
+		unsafe { core::char::from_u32_unchecked(arg) }
+	}
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for char {
+	type SelfInstance = char;
+
+	fn from_ffi_value(_: &mut dyn FunctionContext, arg: u32) -> Result<char> {
+		core::char::from_u32(arg)
+			.ok_or_else(|| format!("Invalid char value provided: {:#x}", arg).into())
+	}
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for char {
+	fn into_ffi_value(self, _: &mut dyn FunctionContext) -> Result<u32> {
+		Ok(self as u32)
+	}
+}
+
+/// `Duration` is passed as `u64`, the number of whole milliseconds.
+///
+/// Sub-millisecond precision is truncated, durations longer than `u64::max_value()` milliseconds
+/// are saturated.
+impl RIType for Duration {
+	type FFIType = u64;
+}
+
+/// The number of whole milliseconds of `duration`, saturated at `u64::max_value()`.
+fn duration_as_millis(duration: &Duration) -> u64 {
+	duration.as_millis().min(u64::max_value() as u128) as u64
+}
+
+#[cfg(not(feature = "std"))]
+impl IntoFFIValue for Duration {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64> {
+		duration_as_millis(self).into()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for Duration {
+	fn from_ffi_value(arg: u64) -> Duration {
+		Duration::from_millis(arg)
+	}
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for Duration {
+	type SelfInstance = Duration;
+
+	fn from_ffi_value(_: &mut dyn FunctionContext, arg: u64) -> Result<Duration> {
+		Ok(Duration::from_millis(arg))
+	}
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for Duration {
+	fn into_ffi_value(self, _: &mut dyn FunctionContext) -> Result<u64> {
+		Ok(duration_as_millis(&self))
+	}
+}
+
+/// `Range<u32>` is passed as `u64`.
+///
+/// The `u64` value is build by `end 32bit << 32 | start 32bit`. Empty ranges like `5..5` keep
+/// their bounds.
+impl RIType for Range<u32> {
+	type FFIType = u64;
+}
+
+/// Pack the bounds of a range into a `u64`, `start` goes into the lower 32 bits.
+fn pack_range_bounds(start: u32, end: u32) -> u64 {
+	(end as u64) << 32 | start as u64
+}
+
+/// Unpack the bounds of a range packed by [`pack_range_bounds`].
+fn unpack_range_bounds(value: u64) -> (u32, u32) {
+	(value as u32, (value >> 32) as u32)
+}
+
+#[cfg(not(feature = "std"))]
+impl IntoFFIValue for Range<u32> {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64> {
+		pack_range_bounds(self.start, self.end).into()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for Range<u32> {
+	fn from_ffi_value(arg: u64) -> Range<u32> {
+		let (start, end) = unpack_range_bounds(arg);
+		start..end
+	}
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for Range<u32> {
+	type SelfInstance = Range<u32>;
+
+	fn from_ffi_value(_: &mut dyn FunctionContext, arg: u64) -> Result<Range<u32>> {
+		let (start, end) = unpack_range_bounds(arg);
+		Ok(start..end)
+	}
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for Range<u32> {
+	fn into_ffi_value(self, _: &mut dyn FunctionContext) -> Result<u64> {
+		Ok(pack_range_bounds(self.start, self.end))
+	}
+}
+
+/// `RangeInclusive<u32>` is passed as `u64`, the same as `Range<u32>`.
+///
+/// A range that was exhausted by iterating it still has its bounds, but is empty. It is passed as
+/// the empty range `1..=0`.
+impl RIType for RangeInclusive<u32> {
+	type FFIType = u64;
+}
+
+/// Pack the bounds of `range` into a `u64`, see [`pack_range_bounds`].
+fn pack_range_inclusive(range: &RangeInclusive<u32>) -> u64 {
+	let (start, end) = (*range.start(), *range.end());
+
+	if start <= end && range.clone().next().is_none() {
+		pack_range_bounds(1, 0)
+	} else {
+		pack_range_bounds(start, end)
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl IntoFFIValue for RangeInclusive<u32> {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64> {
+		pack_range_inclusive(self).into()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for RangeInclusive<u32> {
+	fn from_ffi_value(arg: u64) -> RangeInclusive<u32> {
+		let (start, end) = unpack_range_bounds(arg);
+		start..=end
+	}
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for RangeInclusive<u32> {
+	type SelfInstance = RangeInclusive<u32>;
+
+	fn from_ffi_value(_: &mut dyn FunctionContext, arg: u64) -> Result<RangeInclusive<u32>> {
+		let (start, end) = unpack_range_bounds(arg);
+		Ok(start..=end)
+	}
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for RangeInclusive<u32> {
+	fn into_ffi_value(self, _: &mut dyn FunctionContext) -> Result<u64> {
+		Ok(pack_range_inclusive(&self))
+	}
+}
+
+/// `PhantomData<T>` is passed as `u8`, which is always `0`.
+///
+/// The value carries no data, so the received value is ignored.
+impl<T> RIType for PhantomData<T> {
+	type FFIType = u8;
+}
+
+#[cfg(not(feature = "std"))]
+impl<T> IntoFFIValue for PhantomData<T> {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u8> {
+		0.into()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl<T> FromFFIValue for PhantomData<T> {
+	fn from_ffi_value(_: u8) -> PhantomData<T> {
+		PhantomData
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T> FromFFIValue for PhantomData<T> {
+	type SelfInstance = PhantomData<T>;
+
+	fn from_ffi_value(_: &mut dyn FunctionContext, _: u8) -> Result<PhantomData<T>> {
+		Ok(PhantomData)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T> IntoFFIValue for PhantomData<T> {
+	fn into_ffi_value(self, _: &mut dyn FunctionContext) -> Result<u8> {
+		Ok(0)
+	}
+}
+
+/// `()` is passed as `u8`, which is always `0`.
+///
+/// The value carries no data, so the received value is ignored.
+impl RIType for () {
+	type FFIType = u8;
+}
+
+#[cfg(not(feature = "std"))]
+impl IntoFFIValue for () {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u8> {
+		0.into()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for () {
+	fn from_ffi_value(_: u8) {}
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for () {
+	type SelfInstance = ();
+
+	fn from_ffi_value(_: &mut dyn FunctionContext, _: u8) -> Result<()> {
+		Ok(())
+	}
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for () {
+	fn into_ffi_value(self, _: &mut dyn FunctionContext) -> Result<u8> {
+		Ok(0)
+	}
+}
+
+/// Implement the traits for the given non-zero integer types.
+macro_rules! impl_traits_for_non_zero_primitives {
+	(
+		$(
+			$rty:ty, $fty:ty,
+		)*
+	) => {
+		$(
+			/// The type is passed as the underlying integer.
+			impl RIType for $rty {
+				type FFIType = $fty;
+			}
+
+			#[cfg(not(feature = "std"))]
+			impl IntoFFIValue for $rty {
+				type Owned = ();
+
+				fn into_ffi_value(&self) -> WrappedFFIValue<$fty> {
+					self.get().into()
+				}
+			}
+
+			#[cfg(not(feature = "std"))]
+			impl FromFFIValue for $rty {
+				fn from_ffi_value(arg: $fty) -> $rty {
+					// The host only passes non-zero values to wasm, see the host side
+					// `IntoFFIValue`.
+					unsafe { <$rty>::new_unchecked(arg) }
+				}
+			}
+
+			#[cfg(feature = "std")]
+			impl FromFFIValue for $rty {
+				type SelfInstance = $rty;
+
+				fn from_ffi_value(_: &mut dyn FunctionContext, arg: $fty) -> Result<$rty> {
+					<$rty>::new(arg).ok_or_else(|| {
+						format!("Invalid `{}` value provided: 0", stringify!($rty)).into()
+					})
+				}
+			}
+
+			#[cfg(feature = "std")]
+			impl IntoFFIValue for $rty {
+				fn into_ffi_value(self, _: &mut dyn FunctionContext) -> Result<$fty> {
+					Ok(self.get())
+				}
+			}
+		)*
+	}
+}
+
+impl_traits_for_non_zero_primitives! {
+	NonZeroU16, u16,
+	NonZeroU32, u32,
+	NonZeroU64, u64,
+}
+
 /// The type is passed as `u64`.
 ///
 /// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
 ///
 /// If `T == u8` the length and the pointer are taken directly from the `Self`.
 /// Otherwise `Self` is encoded and the length and the pointer are taken from the encoded vector.
+///
+/// Nested vectors like `Vec<Vec<u8>>` do not need a dedicated representation, their encoding is
+/// already a flat framing: the number of inner vectors followed by `[length][bytes]` for each of
+/// them, with compact lengths that take a single byte for blobs of less than 64 bytes.
 impl<T> RIType for Vec<T> {
 	type FFIType = u64;
 }
@@ -159,16 +640,16 @@ impl<T> RIType for Vec<T> {
 #[cfg(feature = "std")]
 impl<T: 'static + Encode> IntoFFIValue for Vec<T> {
 	fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u64> {
-		let vec: Cow<'_, [u8]> = if TypeId::of::<T>() == TypeId::of::<u8>() {
-			unsafe { Cow::Borrowed(mem::transmute(&self[..])) }
-		} else {
-			Cow::Owned(self.encode())
-		};
+		if TypeId::of::<T>() == TypeId::of::<u8>() {
+			let vec: &[u8] = unsafe { mem::transmute(&self[..]) };
 
-		let ptr = context.allocate_memory(vec.as_ref().len() as u32)?;
-		context.write_memory(ptr, &vec)?;
+			let ptr = context.allocate_memory(vec.len() as u32)?;
+			context.write_memory(ptr, vec)?;
 
-		Ok(pack_ptr_and_len(ptr.into(), vec.len() as u32))
+			Ok(pack_ptr_and_len(ptr.into(), vec.len() as u32))
+		} else {
+			write_encoded(context, &self)
+		}
 	}
 }
 
@@ -193,14 +674,18 @@ impl<T: 'static + Encode> IntoFFIValue for Vec<T> {
 #[cfg(not(feature = "std"))]
 impl<T: 'static + Decode> FromFFIValue for Vec<T> {
 	fn from_ffi_value(arg: u64) -> Vec<T> {
+		Self::try_from_ffi_value(arg).expect("Host to wasm values are encoded correctly; qed")
+	}
+
+	fn try_from_ffi_value(arg: u64) -> sp_std::result::Result<Vec<T>, codec::Error> {
 		let (ptr, len) = unpack_ptr_and_len(arg);
 		let len = len as usize;
 
 		if TypeId::of::<T>() == TypeId::of::<u8>() {
-			unsafe { mem::transmute(Vec::from_raw_parts(ptr as *mut u8, len, len)) }
+			Ok(unsafe { mem::transmute(Vec::from_raw_parts(ptr as *mut u8, len, len)) })
 		} else {
 			let slice = unsafe { slice::from_raw_parts(ptr as *const u8, len) };
-			Self::decode(&mut &slice[..]).expect("Host to wasm values are encoded correctly; qed")
+			Self::decode(&mut &slice[..])
 		}
 	}
 }
@@ -211,6 +696,10 @@ impl<T: 'static + Decode> FromFFIValue for Vec<T> {
 ///
 /// If `T == u8` the length and the pointer are taken directly from the `Self`.
 /// Otherwise `Self` is encoded and the length and the pointer are taken from the encoded vector.
+///
+/// Slices of fixed size arrays like `&[[u8; 32]]` do not need a dedicated representation, their
+/// encoding is the compact number of arrays followed by the flat `N * 32` bytes, as arrays are
+/// encoded without a length.
 impl<T> RIType for [T] {
 	type FFIType = u64;
 }
@@ -220,18 +709,38 @@ impl<T: 'static + Decode> FromFFIValue for [T] {
 	type SelfInstance = Vec<T>;
 
 	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<Vec<T>> {
-		let (ptr, len) = unpack_ptr_and_len(arg);
-
-		let vec = context.read_memory(Pointer::new(ptr), len)?;
+		let (ptr, len) = checked_pointer_and_len(arg, context.memory_size())?;
 
 		if TypeId::of::<T>() == TypeId::of::<u8>() {
+			let vec = context.read_memory(Pointer::new(ptr), len)?;
 			Ok(unsafe { mem::transmute(vec) })
 		} else {
-			Ok(Vec::<T>::decode(&mut &vec[..]).expect("Wasm to host values are encoded correctly; qed"))
+			let data = read_memory_small(context, Pointer::new(ptr), len)?;
+			Vec::<T>::decode(&mut &data[..])
+				.map_err(|e| format!("Could not decode value from wasm: {}", e.what()).into())
 		}
 	}
 }
 
+#[cfg(feature = "std")]
+impl WithFFIValue for [u8] {
+	fn with_ffi_value<R>(
+		context: &dyn FunctionContext,
+		arg: u64,
+		f: impl FnOnce(&[u8]) -> R,
+	) -> Result<R> {
+		let (ptr, len) = checked_pointer_and_len(arg, context.memory_size())?;
+
+		let mut f = Some(f);
+		let mut res = None;
+		context.with_memory_slice(Pointer::new(ptr), len, &mut |data| {
+			res = f.take().map(|f| f(data));
+		})?;
+
+		res.ok_or_else(|| "`with_memory_slice` did not call the given function".into())
+	}
+}
+
 #[cfg(feature = "std")]
 impl IntoPreallocatedFFIValue for [u8] {
 	type SelfInstance = Vec<u8>;
@@ -249,27 +758,135 @@ impl IntoPreallocatedFFIValue for [u8] {
 					"Preallocated buffer is not big enough (given {} vs needed {})!",
 					len,
 					self_instance.len()
-				)
+				).into()
 			)
 		} else {
-			context.write_memory(Pointer::new(ptr), &self_instance)
+			context.write_memory(Pointer::new(ptr), &self_instance).map_err(Into::into)
 		}
 	}
 }
 
+/// The data is written to the start of the buffer of the wasm side `Vec<u8>`. The length of that
+/// vector is not changed, as the host can not reallocate wasm memory.
+#[cfg(feature = "std")]
+impl IntoPreallocatedFFIValue for Vec<u8> {
+	type SelfInstance = Vec<u8>;
+
+	fn into_preallocated_ffi_value(
+		self_instance: Self::SelfInstance,
+		context: &mut dyn FunctionContext,
+		allocated: u64,
+	) -> Result<()> {
+		<[u8]>::into_preallocated_ffi_value(self_instance, context, allocated)
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl<T: 'static + Encode> IntoFFIValue for [T] {
+	type Owned = Vec<u8>;
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64, Vec<u8>> {
+		if TypeId::of::<T>() == TypeId::of::<u8>() {
+			let slice = unsafe { mem::transmute::<&[T], &[u8]>(self) };
+			pack_ptr_and_len(slice.as_ptr() as u32, slice.len() as u32).into()
+		} else {
+			let data = self.encode();
+			let ffi_value = pack_ptr_and_len(data.as_ptr() as u32, data.len() as u32);
+			(ffi_value, data).into()
+		}
+	}
+}
+
+/// The type is passed as `u64`.
+///
+/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
+///
+/// The length and the pointer are taken directly from the `Self`.
+impl RIType for Box<[u8]> {
+	type FFIType = u64;
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for Box<[u8]> {
+	fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u64> {
+		let ptr = context.allocate_memory(self.len() as u32)?;
+		context.write_memory(ptr, &self)?;
+
+		Ok(pack_ptr_and_len(ptr.into(), self.len() as u32))
+	}
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for Box<[u8]> {
+	type SelfInstance = Box<[u8]>;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<Box<[u8]>> {
+		<[u8] as FromFFIValue>::from_ffi_value(context, arg).map(Vec::into_boxed_slice)
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl IntoFFIValue for Box<[u8]> {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64> {
+		pack_ptr_and_len(self.as_ptr() as u32, self.len() as u32).into()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for Box<[u8]> {
+	fn from_ffi_value(arg: u64) -> Box<[u8]> {
+		let (ptr, len) = unpack_ptr_and_len(arg);
+
+		// The host allocated exactly `len` bytes in the wasm heap and passes the ownership to us.
+		unsafe { Box::from_raw(slice::from_raw_parts_mut(ptr as *mut u8, len as usize)) }
+	}
+}
+
+/// The type is passed as `u64`.
+///
+/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
+///
+/// The length and the pointer are taken directly from the borrowed or owned bytes, so borrowed
+/// bytes do not need to be copied into a `Vec<u8>` first. The receiving side always gets
+/// `Cow::Owned`.
+impl RIType for Cow<'_, [u8]> {
+	type FFIType = u64;
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for Cow<'_, [u8]> {
+	fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u64> {
+		let ptr = context.allocate_memory(self.len() as u32)?;
+		context.write_memory(ptr, &self)?;
+
+		Ok(pack_ptr_and_len(ptr.into(), self.len() as u32))
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'a> FromFFIValue for Cow<'a, [u8]> {
+	type SelfInstance = Cow<'a, [u8]>;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<Cow<'a, [u8]>> {
+		<[u8] as FromFFIValue>::from_ffi_value(context, arg).map(Cow::Owned)
+	}
+}
+
 #[cfg(not(feature = "std"))]
-impl<T: 'static + Encode> IntoFFIValue for [T] {
-	type Owned = Vec<u8>;
+impl IntoFFIValue for Cow<'_, [u8]> {
+	type Owned = ();
 
-	fn into_ffi_value(&self) -> WrappedFFIValue<u64, Vec<u8>> {
-		if TypeId::of::<T>() == TypeId::of::<u8>() {
-			let slice = unsafe { mem::transmute::<&[T], &[u8]>(self) };
-			pack_ptr_and_len(slice.as_ptr() as u32, slice.len() as u32).into()
-		} else {
-			let data = self.encode();
-			let ffi_value = pack_ptr_and_len(data.as_ptr() as u32, data.len() as u32);
-			(ffi_value, data).into()
-		}
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64> {
+		pack_ptr_and_len(self.as_ptr() as u32, self.len() as u32).into()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for Cow<'_, [u8]> {
+	fn from_ffi_value(arg: u64) -> Self {
+		Cow::Owned(<Vec<u8>>::from_ffi_value(arg))
 	}
 }
 
@@ -284,11 +901,14 @@ macro_rules! impl_traits_for_arrays {
 		$(
 			/// The type is passed as `u32`.
 			///
-			/// The `u32` is the pointer to the array.
+			/// The `u32` is the pointer to the array. The bytes are copied as they are, so they do
+			/// not depend on the byte order of the host.
 			impl RIType for [u8; $n] {
 				type FFIType = u32;
 			}
 
+			impl crate::nullable::ByteArray for [u8; $n] {}
+
 			#[cfg(not(feature = "std"))]
 			impl IntoFFIValue for [u8; $n] {
 				type Owned = ();
@@ -301,13 +921,11 @@ macro_rules! impl_traits_for_arrays {
 			#[cfg(not(feature = "std"))]
 			impl FromFFIValue for [u8; $n] {
 				fn from_ffi_value(arg: u32) -> [u8; $n] {
-					let mut res = [0u8; $n];
-					res.copy_from_slice(unsafe { slice::from_raw_parts(arg as *const u8, $n) });
-
-					// Make sure we free the pointer.
-					let _ = unsafe { Box::from_raw(arg as *mut u8) };
-
-					res
+					// The host allocated the array in the wasm heap (see the host side
+					// `IntoFFIValue`) and passes the ownership to us. Taking it back as a box of the
+					// full array makes sure the memory is freed with the layout it was allocated
+					// with.
+					*unsafe { Box::from_raw(arg as *mut [u8; $n]) }
 				}
 			}
 
@@ -317,6 +935,15 @@ macro_rules! impl_traits_for_arrays {
 
 				fn from_ffi_value(context: &mut dyn FunctionContext, arg: u32) -> Result<[u8; $n]> {
 					let data = context.read_memory(Pointer::new(arg), $n)?;
+					if data.len() != $n {
+						return Err(format!(
+							"Invalid length of read `[u8; {}]` (given {} vs expected {})!",
+							$n,
+							data.len(),
+							$n,
+						).into())
+					}
+
 					let mut res = [0u8; $n];
 					res.copy_from_slice(&data);
 					Ok(res)
@@ -332,6 +959,9 @@ macro_rules! impl_traits_for_arrays {
 				}
 			}
 
+			/// The ffi value only carries the pointer to the array. That it points to `N` bytes is
+			/// guaranteed by the `&mut [u8; N]` on the wasm side, so it is only checked that these
+			/// bytes are part of the wasm memory.
 			#[cfg(feature = "std")]
 			impl IntoPreallocatedFFIValue for [u8; $n] {
 				type SelfInstance = [u8; $n];
@@ -341,7 +971,16 @@ macro_rules! impl_traits_for_arrays {
 					context: &mut dyn FunctionContext,
 					allocated: u32,
 				) -> Result<()> {
-					context.write_memory(Pointer::new(allocated), &self_instance)
+					let (ptr, _) = checked_pointer_and_len(
+						pack_ptr_and_len(allocated, $n),
+						context.memory_size(),
+					).map_err(|_| format!(
+						"Preallocated array of {} bytes at {:#x} is out of bounds!",
+						$n,
+						allocated,
+					))?;
+
+					context.write_memory(Pointer::new(ptr), &self_instance).map_err(Into::into)
 				}
 			}
 		)*
@@ -353,16 +992,210 @@ impl_traits_for_arrays! {
 	27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50,
 	51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74,
 	75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 89, 90, 91, 92, 93, 94, 95, 96,
+	128, 160, 256,
+}
+
+/// Implement the traits for arrays of the given element types and sizes, `[u8; N]` is handled by
+/// `impl_traits_for_arrays!`.
+///
+/// The element types are required to have a fixed size SCALE encoding.
+macro_rules! impl_traits_for_codec_arrays {
+	( [ $( $ty:ty ),* $(,)? ] $sizes:tt ) => {
+		$( impl_traits_for_codec_arrays!(@sizes $ty, $sizes); )*
+	};
+	( @sizes $ty:ty, [ $( $n:expr ),* $(,)? ] ) => {
+		$(
+			/// The type is passed as `u64`.
+			///
+			/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
+			///
+			/// `Self` is encoded and the length and the pointer are taken from the encoded vector.
+			/// The elements are encoded little-endian, whatever the byte order of the host.
+			impl RIType for [$ty; $n] {
+				type FFIType = u64;
+			}
+
+			#[cfg(not(feature = "std"))]
+			impl IntoFFIValue for [$ty; $n] {
+				type Owned = Vec<u8>;
+
+				fn into_ffi_value(&self) -> WrappedFFIValue<u64, Vec<u8>> {
+					let data = self.encode();
+					let ffi_value = pack_ptr_and_len(data.as_ptr() as u32, data.len() as u32);
+					(ffi_value, data).into()
+				}
+			}
+
+			#[cfg(not(feature = "std"))]
+			impl FromFFIValue for [$ty; $n] {
+				fn from_ffi_value(arg: u64) -> [$ty; $n] {
+					Self::try_from_ffi_value(arg)
+						.expect("Host to wasm values are encoded correctly; qed")
+				}
+
+				fn try_from_ffi_value(
+					arg: u64,
+				) -> sp_std::result::Result<[$ty; $n], codec::Error> {
+					let (ptr, len) = unpack_ptr_and_len(arg);
+					let len = len as usize;
+
+					// The host allocated the encoded array in the wasm heap and passes the
+					// ownership to us.
+					let data = unsafe { Vec::from_raw_parts(ptr as *mut u8, len, len) };
+					<[$ty; $n]>::decode(&mut &data[..])
+				}
+			}
+
+			#[cfg(feature = "std")]
+			impl FromFFIValue for [$ty; $n] {
+				type SelfInstance = [$ty; $n];
+
+				fn from_ffi_value(
+					context: &mut dyn FunctionContext,
+					arg: u64,
+				) -> Result<[$ty; $n]> {
+					let (ptr, len) = checked_pointer_and_len(arg, context.memory_size())?;
+					let data = context.read_memory(Pointer::new(ptr), len)?;
+
+					let expected = mem::size_of::<[$ty; $n]>();
+					if data.len() != expected {
+						return Err(format!(
+							"Invalid length of encoded `[{}; {}]` (given {} vs expected {})!",
+							stringify!($ty),
+							$n,
+							data.len(),
+							expected,
+						).into())
+					}
+
+					<[$ty; $n]>::decode(&mut &data[..])
+						.map_err(|e| {
+							format!("Could not decode value from wasm: {}", e.what()).into()
+						})
+				}
+			}
+
+			#[cfg(feature = "std")]
+			impl IntoFFIValue for [$ty; $n] {
+				fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u64> {
+					write_encoded(context, &self)
+				}
+			}
+		)*
+	};
+}
+
+impl_traits_for_codec_arrays! {
+	[u16, u32, u64, u128, i8, i16, i32, i64, i128]
+	[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 32]
+}
+
+/// Implement the traits for the given collections, which are passed SCALE encoded.
+macro_rules! impl_traits_for_codec_collections {
+	( $( $ty:ident < $( $param:ident ),* > ),* $(,)? ) => {
+		$(
+			/// The type is passed as `u64`.
+			///
+			/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
+			///
+			/// `Self` is encoded and the length and the pointer are taken from the encoded vector.
+			/// The elements are encoded in their sorted order, so equal collections always have
+			/// the same encoding.
+			impl<$( $param ),*> RIType for $ty<$( $param ),*> {
+				type FFIType = u64;
+			}
+
+			#[cfg(not(feature = "std"))]
+			impl<$( $param ),*> IntoFFIValue for $ty<$( $param ),*> where Self: Encode {
+				type Owned = Vec<u8>;
+
+				fn into_ffi_value(&self) -> WrappedFFIValue<u64, Vec<u8>> {
+					let data = self.encode();
+					let ffi_value = pack_ptr_and_len(data.as_ptr() as u32, data.len() as u32);
+					(ffi_value, data).into()
+				}
+			}
+
+			#[cfg(not(feature = "std"))]
+			impl<$( $param ),*> FromFFIValue for $ty<$( $param ),*> where Self: Decode {
+				fn from_ffi_value(arg: u64) -> Self {
+					Self::try_from_ffi_value(arg)
+						.expect("Host to wasm values are encoded correctly; qed")
+				}
+
+				fn try_from_ffi_value(arg: u64) -> sp_std::result::Result<Self, codec::Error> {
+					let (ptr, len) = unpack_ptr_and_len(arg);
+					let len = len as usize;
+
+					// The host allocated the encoded collection in the wasm heap and passes the
+					// ownership to us.
+					let data = unsafe { Vec::from_raw_parts(ptr as *mut u8, len, len) };
+					Self::decode(&mut &data[..])
+				}
+			}
+
+			#[cfg(feature = "std")]
+			impl<$( $param ),*> FromFFIValue for $ty<$( $param ),*> where Self: Decode {
+				type SelfInstance = Self;
+
+				fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<Self> {
+					let (ptr, len) = checked_pointer_and_len(arg, context.memory_size())?;
+					let data = read_memory_small(context, Pointer::new(ptr), len)?;
+
+					Self::decode(&mut &data[..]).map_err(|e| {
+						format!("Could not decode value from wasm: {}", e.what()).into()
+					})
+				}
+			}
+
+			#[cfg(feature = "std")]
+			impl<$( $param ),*> IntoFFIValue for $ty<$( $param ),*> where Self: Encode {
+				fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u64> {
+					write_encoded(context, &self)
+				}
+			}
+		)*
+	};
 }
 
+impl_traits_for_codec_collections!(BTreeMap<K, V>, BTreeSet<T>);
+
 impl<T: codec::Codec, E: codec::Codec> PassBy for sp_std::result::Result<T, E> {
 	type PassBy = Codec<Self>;
 }
 
+/// `Option<T>` is passed SCALE encoded. Small primitives can be passed without SCALE encoding by
+/// using [`CompactOption`](crate::compact_option::CompactOption) instead, byte arrays and byte
+/// vectors as a nullable pointer by using [`NullableArray`](crate::nullable::NullableArray) and
+/// [`NullableBytes`](crate::nullable::NullableBytes).
 impl<T: codec::Codec> PassBy for Option<T> {
 	type PassBy = Codec<Self>;
 }
 
+/// `Compact<u32>` is passed SCALE encoded, which is its compact encoding.
+impl PassBy for Compact<u32> {
+	type PassBy = Codec<Self>;
+}
+
+/// `Compact<u64>` is passed SCALE encoded, which is its compact encoding.
+impl PassBy for Compact<u64> {
+	type PassBy = Codec<Self>;
+}
+
+/// Implement `PassBy` with `Codec` for tuples of the given types and all their suffixes.
+macro_rules! impl_pass_by_codec_for_tuples {
+	( $first:ident $(, $rest:ident )* $(,)? ) => {
+		impl<$first: codec::Codec, $( $rest: codec::Codec ),*> PassBy for ($first, $( $rest, )*) {
+			type PassBy = Codec<Self>;
+		}
+
+		impl_pass_by_codec_for_tuples!( $( $rest ),* );
+	};
+	() => {};
+}
+
+impl_pass_by_codec_for_tuples!(A, B, C, D, E, F, G, H);
+
 /// Implement `PassBy` with `Inner` for the given fixed sized hash types.
 macro_rules! for_primitive_types {
 	{ $( $hash:ident $n:expr ),* $(,)? } => {
@@ -429,6 +1262,50 @@ impl IntoFFIValue for str {
 	}
 }
 
+/// The type is passed as `u64`.
+///
+/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
+///
+/// The length and the pointer are taken directly from the utf8 bytes of `Self`.
+impl RIType for String {
+	type FFIType = u64;
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for String {
+	type SelfInstance = String;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<String> {
+		<str as FromFFIValue>::from_ffi_value(context, arg)
+	}
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for String {
+	fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u64> {
+		self.into_bytes().into_ffi_value(context)
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl IntoFFIValue for String {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64, ()> {
+		self[..].into_ffi_value()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for String {
+	fn from_ffi_value(arg: u64) -> String {
+		let bytes = <Vec<u8> as FromFFIValue>::from_ffi_value(arg);
+
+		// The host only passes `String`s to wasm, so the data is always valid utf8.
+		unsafe { String::from_utf8_unchecked(bytes) }
+	}
+}
+
 #[cfg(feature = "std")]
 impl<T: sp_wasm_interface::PointerType> RIType for Pointer<T> {
 	type FFIType = u32;
@@ -471,3 +1348,501 @@ impl<T: sp_wasm_interface::PointerType> IntoFFIValue for Pointer<T> {
 		Ok(self.into())
 	}
 }
+
+#[cfg(all(test, feature = "std"))]
+pub(crate) mod tests {
+	use super::*;
+	use std::{alloc::{GlobalAlloc, Layout, System}, borrow::Cow, cell::Cell};
+	use sp_wasm_interface::{Result, Sandbox, WordSize};
+
+	thread_local! {
+		static ALLOCATIONS: Cell<usize> = Cell::new(0);
+	}
+
+	/// An allocator that counts the allocations of each thread.
+	struct CountingAllocator;
+
+	unsafe impl GlobalAlloc for CountingAllocator {
+		unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+			// The thread local is not available anymore while the thread shuts down.
+			let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+			System.alloc(layout)
+		}
+
+		unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+			System.dealloc(ptr, layout)
+		}
+	}
+
+	#[global_allocator]
+	static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+	/// A `FunctionContext` over a plain buffer that counts the bytes copied out of the memory.
+	///
+	/// Allocating memory is not supported and panics.
+	pub(crate) struct TestContext {
+		pub(crate) memory: Vec<u8>,
+		pub(crate) copied: Cell<usize>,
+	}
+
+	impl TestContext {
+		/// A context over the given `memory` that did not copy any bytes yet.
+		pub(crate) fn new(memory: Vec<u8>) -> Self {
+			TestContext { memory, copied: Cell::new(0) }
+		}
+	}
+
+	impl FunctionContext for TestContext {
+		fn read_memory_into(&self, address: Pointer<u8>, dest: &mut [u8]) -> Result<()> {
+			let start = u32::from(address) as usize;
+			dest.copy_from_slice(&self.memory[start..start + dest.len()]);
+			self.copied.set(self.copied.get() + dest.len());
+			Ok(())
+		}
+
+		fn with_memory_slice(
+			&self,
+			address: Pointer<u8>,
+			size: WordSize,
+			f: &mut dyn FnMut(&[u8]),
+		) -> Result<()> {
+			let start = u32::from(address) as usize;
+			f(&self.memory[start..start + size as usize]);
+			Ok(())
+		}
+
+		fn memory_size(&self) -> WordSize {
+			self.memory.len() as WordSize
+		}
+
+		fn write_memory(&mut self, address: Pointer<u8>, data: &[u8]) -> Result<()> {
+			let start = u32::from(address) as usize;
+			self.memory[start..start + data.len()].copy_from_slice(data);
+			Ok(())
+		}
+
+		fn allocate_memory(&mut self, _: WordSize) -> Result<Pointer<u8>> {
+			unimplemented!()
+		}
+
+		fn deallocate_memory(&mut self, _: Pointer<u8>) -> Result<()> {
+			unimplemented!()
+		}
+
+		fn sandbox(&mut self) -> &mut dyn Sandbox {
+			unimplemented!()
+		}
+	}
+
+	#[test]
+	fn with_ffi_value_does_not_copy_the_memory() {
+		let mut context = TestContext::new((0..2048u32).map(|i| i as u8).collect());
+		let arg = pack_ptr_and_len(512, 1024);
+
+		let borrowed = <[u8]>::with_ffi_value(&context, arg, sp_core::blake2_256).unwrap();
+		assert_eq!(context.copied.get(), 0);
+
+		let owned = <[u8]>::from_ffi_value(&mut context, arg).unwrap();
+		assert_eq!(context.copied.get(), 1024);
+
+		assert_eq!(borrowed, sp_core::blake2_256(&owned));
+	}
+
+	#[test]
+	fn with_ffi_value_rejects_out_of_bounds_slices() {
+		let context = TestContext::new(vec![0; 1024]);
+
+		for arg in &[pack_ptr_and_len(1000, 25), pack_ptr_and_len(u32::max_value(), 2)] {
+			assert_eq!(
+				<[u8]>::with_ffi_value(&context, *arg, |_| ()),
+				Err("pointer+length out of bounds".into()),
+			);
+		}
+	}
+
+	#[crate::runtime_interface]
+	trait Borrowing {
+		fn weighted_sum(first: &[u8], factor: u32, second: &[u8]) -> u64 {
+			first.iter().chain(second).map(|b| *b as u64 * factor as u64).sum()
+		}
+	}
+
+	#[test]
+	fn host_functions_borrow_byte_slice_arguments() {
+		use sp_wasm_interface::{HostFunctions as _, Value};
+
+		let mut context = TestContext::new((0..2048u32).map(|i| i as u8).collect());
+		let function = borrowing::HostFunctions::host_functions()[0];
+		let args = [
+			Value::I64(pack_ptr_and_len(0, 1024) as i64),
+			Value::I32(2),
+			Value::I64(pack_ptr_and_len(1024, 1024) as i64),
+		];
+
+		let result = function.execute(&mut context, &mut args.iter().cloned()).unwrap();
+		let expected = context.memory.iter().map(|b| *b as u64 * 2).sum::<u64>();
+		assert_eq!(result, Some(Value::I64(expected as i64)));
+		assert_eq!(context.copied.get(), 0);
+	}
+
+	#[test]
+	fn nested_vec_should_be_passed_as_flat_framing() {
+		let blobs = (0..100u8).map(|i| vec![i; i as usize % 8]).collect::<Vec<_>>();
+
+		// The compact number of blobs, then one length byte in front of each blob.
+		let mut framing = Compact(100u32).encode();
+		for blob in &blobs {
+			framing.push((blob.len() as u8) << 2);
+			framing.extend_from_slice(blob);
+		}
+		assert_eq!(blobs.encode(), framing);
+		assert_eq!(framing.len(), 2 + blobs.len() + blobs.iter().map(Vec::len).sum::<usize>());
+
+		let len = framing.len() as u32;
+		let mut context = TestContext::new(framing);
+		let arg = pack_ptr_and_len(0, len);
+		assert_eq!(Vec::<Vec<u8>>::from_ffi_value(&mut context, arg), Ok(blobs));
+		assert_eq!(context.copied.get(), len as usize);
+	}
+
+	#[test]
+	fn slice_of_arrays_should_be_passed_flat() {
+		let hashes = [[1u8; 32], [2; 32], [3; 32]];
+
+		// The compact number of hashes, then their bytes without any framing.
+		let mut flat = Compact(3u32).encode();
+		flat.extend(hashes.iter().flat_map(|h| h.iter()));
+		assert_eq!(hashes[..].encode(), flat);
+		assert_eq!(flat.len(), 1 + hashes.len() * 32);
+
+		let len = flat.len() as u32;
+		let mut context = TestContext::new(flat);
+		let arg = pack_ptr_and_len(0, len);
+		assert_eq!(<[[u8; 32]]>::from_ffi_value(&mut context, arg), Ok(hashes.to_vec()));
+		assert_eq!(context.copied.get(), len as usize);
+	}
+
+	#[test]
+	fn vec_should_be_written_into_preallocated_buffer() {
+		let mut context = TestContext::new(vec![0; 16]);
+		let mut write = |data: Vec<u8>, ptr, len| {
+			Vec::into_preallocated_ffi_value(data, &mut context, pack_ptr_and_len(ptr, len))
+		};
+
+		// exact length.
+		assert_eq!(write(vec![1; 4], 0, 4), Ok(()));
+		// the buffer is bigger than the data.
+		assert_eq!(write(vec![2; 4], 8, 8), Ok(()));
+		// the buffer is smaller than the data.
+		assert_eq!(
+			write(vec![3; 4], 4, 3),
+			Err("Preallocated buffer is not big enough (given 3 vs needed 4)!".into()),
+		);
+
+		assert_eq!(context.memory, [1, 1, 1, 1, 0, 0, 0, 0, 2, 2, 2, 2, 0, 0, 0, 0]);
+	}
+
+	#[test]
+	fn preallocated_array_out_of_bounds_should_error() {
+		let mut context = TestContext::new(vec![0; 64]);
+
+		assert!(<[u8; 16]>::into_preallocated_ffi_value([1; 16], &mut context, 48).is_ok());
+		assert_eq!(&context.memory[48..], &[1; 16]);
+
+		assert_eq!(
+			<[u8; 32]>::into_preallocated_ffi_value([2; 32], &mut context, 48).unwrap_err(),
+			"Preallocated array of 32 bytes at 0x30 is out of bounds!",
+		);
+		assert_eq!(
+			<[u8; 32]>::into_preallocated_ffi_value([2; 32], &mut context, u32::max_value()),
+			Err("Preallocated array of 32 bytes at 0xffffffff is out of bounds!".into()),
+		);
+		assert!(context.memory[..48].iter().all(|v| *v == 0));
+		assert_eq!(&context.memory[48..], &[1; 16]);
+	}
+
+	#[test]
+	fn integer_newtype_should_be_passed_as_its_inner_value() {
+		#[derive(crate::pass_by::PassByInner, PartialEq, Debug)]
+		struct Weight(u64);
+
+		let mut context = TestContext::new(Vec::new());
+		let allocations = ALLOCATIONS.with(Cell::get);
+
+		let ffi_value: u64 = Weight(42).into_ffi_value(&mut context).unwrap();
+		let weight = Weight::from_ffi_value(&mut context, ffi_value).unwrap();
+		assert_eq!(ALLOCATIONS.with(Cell::get), allocations);
+
+		assert_eq!(ffi_value, 42);
+		assert_eq!(weight, Weight(42));
+		assert_eq!(context.copied.get(), 0);
+	}
+
+	#[test]
+	fn borrowed_and_owned_cow_should_write_the_same_bytes() {
+		static STATIC: [u8; 5] = [1, 2, 3, 4, 5];
+
+		// Reserve the wasm memory up front, so the context does not allocate on the host.
+		let mut context = crate::host::tests::CountingContext::default();
+		context.memory.reserve(16);
+
+		let allocations = ALLOCATIONS.with(Cell::get);
+		let borrowed = Cow::Borrowed(&STATIC[..]).into_ffi_value(&mut context).unwrap();
+		assert_eq!(ALLOCATIONS.with(Cell::get), allocations);
+		let owned = Cow::<[u8]>::Owned(STATIC.to_vec()).into_ffi_value(&mut context).unwrap();
+
+		let (borrowed_ptr, borrowed_len) = unpack_ptr_and_len(borrowed);
+		let (owned_ptr, owned_len) = unpack_ptr_and_len(owned);
+		assert_eq!((borrowed_len, owned_len), (5, 5));
+		assert_eq!(&context.memory[borrowed_ptr as usize..][..5], &STATIC[..]);
+		assert_eq!(&context.memory[owned_ptr as usize..][..5], &STATIC[..]);
+
+		let read = Cow::<[u8]>::from_ffi_value(&mut context, owned).unwrap();
+		assert_eq!(read, Cow::<[u8]>::Owned(STATIC.to_vec()));
+	}
+
+	#[test]
+	fn static_error_message_should_not_allocate() {
+		let mut context = TestContext::new(vec![0; 16]);
+		let arg = pack_ptr_and_len(8, 16);
+
+		let allocations = ALLOCATIONS.with(Cell::get);
+		let error = <[u8]>::from_ffi_value(&mut context, arg).unwrap_err();
+		assert_eq!(ALLOCATIONS.with(Cell::get), allocations);
+
+		assert_eq!(error, "pointer+length out of bounds");
+		assert!(if let Cow::Borrowed(_) = error { true } else { false });
+	}
+
+	#[test]
+	fn non_zero_primitives_should_reject_zero() {
+		let mut context = TestContext::new(Vec::new());
+
+		let value = NonZeroU32::new(7).unwrap();
+		let ffi_value = value.into_ffi_value(&mut context).unwrap();
+		assert_eq!(ffi_value, 7);
+		assert_eq!(NonZeroU32::from_ffi_value(&mut context, ffi_value), Ok(value));
+		assert_eq!(NonZeroU16::from_ffi_value(&mut context, 1), Ok(NonZeroU16::new(1).unwrap()));
+
+		assert_eq!(
+			NonZeroU32::from_ffi_value(&mut context, 0),
+			Err("Invalid `NonZeroU32` value provided: 0".into()),
+		);
+		assert_eq!(
+			NonZeroU64::from_ffi_value(&mut context, 0),
+			Err("Invalid `NonZeroU64` value provided: 0".into()),
+		);
+	}
+
+	#[test]
+	fn wide_primitives_should_be_read_as_little_endian_halves() {
+		let mut memory = 5u64.to_le_bytes().to_vec();
+		memory.extend_from_slice(&7u64.to_le_bytes());
+		memory.extend_from_slice(&(-2i64 as u64).to_le_bytes());
+		memory.extend_from_slice(&u64::max_value().to_le_bytes());
+		let mut context = TestContext::new(memory);
+
+		assert_eq!(
+			u128::from_ffi_value(&mut context, pack_ptr_and_len(0, 16)).unwrap(),
+			7 << 64 | 5,
+		);
+		assert_eq!(i128::from_ffi_value(&mut context, pack_ptr_and_len(16, 16)).unwrap(), -2);
+		assert_eq!(
+			u128::from_ffi_value(&mut context, pack_ptr_and_len(0, 8)),
+			Err("Invalid length for `u128` (given 8 vs expected 16)!".into()),
+		);
+	}
+
+	#[test]
+	fn arrays_should_not_depend_on_the_byte_order_of_the_host() {
+		let encoded = [0x0102_0304u32, 0x0506_0708].encode();
+		let mut memory = vec![1u8, 2, 3, 4];
+		memory.extend_from_slice(&encoded);
+		let mut context = TestContext::new(memory);
+
+		assert_eq!(<[u8; 4]>::from_ffi_value(&mut context, 0).unwrap(), [1, 2, 3, 4]);
+
+		let values = <[u32; 2]>::from_ffi_value(&mut context, pack_ptr_and_len(4, 8)).unwrap();
+		assert_eq!(values, [0x0102_0304, 0x0506_0708]);
+		// reading the encoded elements in the byte order of a big-endian host would swap them.
+		let mut first = [0u8; 4];
+		first.copy_from_slice(&encoded[..4]);
+		assert_eq!(u32::from_be_bytes(first), values[0].swap_bytes());
+	}
+
+	#[test]
+	fn duration_should_be_passed_as_millis() {
+		let mut context = TestContext::new(Vec::new());
+		let mut round_trip = |duration: Duration| {
+			let ffi_value = duration.into_ffi_value(&mut context).unwrap();
+			(ffi_value, Duration::from_ffi_value(&mut context, ffi_value).unwrap())
+		};
+
+		let three_days = Duration::from_secs(3 * 24 * 60 * 60);
+		assert_eq!(round_trip(Duration::from_secs(0)), (0, Duration::from_secs(0)));
+		assert_eq!(round_trip(Duration::from_millis(250)), (250, Duration::from_millis(250)));
+		assert_eq!(round_trip(three_days), (259_200_000, three_days));
+
+		assert_eq!(round_trip(Duration::from_micros(1_999)), (1, Duration::from_millis(1)));
+		assert_eq!(
+			round_trip(Duration::from_secs(u64::max_value())),
+			(u64::max_value(), Duration::from_millis(u64::max_value())),
+		);
+	}
+
+	#[test]
+	fn ranges_should_be_passed_as_their_bounds() {
+		let mut context = TestContext::new(Vec::new());
+
+		let ffi_value = (0..10).into_ffi_value(&mut context).unwrap();
+		assert_eq!(ffi_value, 10 << 32);
+		assert_eq!(Range::from_ffi_value(&mut context, ffi_value).unwrap(), 0..10);
+
+		let ffi_value = (5..5).into_ffi_value(&mut context).unwrap();
+		assert_eq!(Range::from_ffi_value(&mut context, ffi_value).unwrap(), 5..5);
+
+		let ffi_value = (0..=u32::max_value()).into_ffi_value(&mut context).unwrap();
+		assert_eq!(ffi_value, 0xffff_ffff_0000_0000);
+		assert_eq!(
+			RangeInclusive::from_ffi_value(&mut context, ffi_value).unwrap(),
+			0..=u32::max_value(),
+		);
+		assert_eq!(context.copied.get(), 0);
+	}
+
+	#[test]
+	fn exhausted_range_inclusive_should_be_passed_as_empty() {
+		let mut context = TestContext::new(Vec::new());
+		let mut range = 5..=5;
+		assert_eq!(range.next(), Some(5));
+
+		let ffi_value = range.into_ffi_value(&mut context).unwrap();
+		let mut range = RangeInclusive::from_ffi_value(&mut context, ffi_value).unwrap();
+		assert_eq!(range, 1..=0);
+		assert_eq!(range.next(), None);
+
+		let ffi_value = (7..=3).into_ffi_value(&mut context).unwrap();
+		assert_eq!(RangeInclusive::from_ffi_value(&mut context, ffi_value).unwrap(), 7..=3);
+	}
+
+	#[test]
+	fn compact_should_be_passed_in_its_compact_encoding() {
+		for (value, width) in &[(0u32, 1), (63, 1), (64, 2), (16383, 2), (16384, 4)] {
+			let mut context = TestContext::new(Compact(*value).encode());
+			assert_eq!(context.memory.len(), *width);
+
+			let arg = pack_ptr_and_len(0, *width as u32);
+			assert_eq!(Compact::<u32>::from_ffi_value(&mut context, arg), Ok(Compact(*value)));
+			assert_eq!(
+				Compact::<u64>::from_ffi_value(&mut context, arg),
+				Ok(Compact(*value as u64)),
+			);
+		}
+	}
+
+	#[test]
+	fn phantom_data_should_be_passed_as_zero() {
+		// The context panics on any memory allocation.
+		let mut context = TestContext::new(Vec::new());
+
+		assert_eq!(PhantomData::<Vec<u8>>.into_ffi_value(&mut context), Ok(0));
+		assert_eq!(PhantomData::<Vec<u8>>::from_ffi_value(&mut context, 3), Ok(PhantomData));
+		assert_eq!(context.copied.get(), 0);
+	}
+
+	#[test]
+	fn unit_should_be_passed_as_zero() {
+		// The context panics on any memory allocation.
+		let mut context = TestContext::new(Vec::new());
+
+		assert_eq!(().into_ffi_value(&mut context), Ok(0));
+		assert_eq!(<()>::from_ffi_value(&mut context, 3), Ok(()));
+		assert_eq!(context.copied.get(), 0);
+	}
+
+	/// A `FunctionContext` that reads one byte less than requested.
+	struct ShortReadContext;
+
+	impl FunctionContext for ShortReadContext {
+		fn read_memory(&self, _: Pointer<u8>, size: WordSize) -> Result<Vec<u8>> {
+			Ok(vec![0; size as usize - 1])
+		}
+
+		fn read_memory_into(&self, _: Pointer<u8>, _: &mut [u8]) -> Result<()> {
+			unimplemented!()
+		}
+
+		fn memory_size(&self) -> WordSize {
+			unimplemented!()
+		}
+
+		fn write_memory(&mut self, _: Pointer<u8>, _: &[u8]) -> Result<()> {
+			unimplemented!()
+		}
+
+		fn allocate_memory(&mut self, _: WordSize) -> Result<Pointer<u8>> {
+			unimplemented!()
+		}
+
+		fn deallocate_memory(&mut self, _: Pointer<u8>) -> Result<()> {
+			unimplemented!()
+		}
+
+		fn sandbox(&mut self) -> &mut dyn Sandbox {
+			unimplemented!()
+		}
+	}
+
+	#[test]
+	fn short_array_read_should_error() {
+		assert_eq!(
+			<[u8; 32]>::from_ffi_value(&mut ShortReadContext, 8),
+			Err("Invalid length of read `[u8; 32]` (given 31 vs expected 32)!".into()),
+		);
+	}
+
+	#[test]
+	fn arrays_of_representative_sizes_should_be_passed_by_pointer() {
+		let memory = (0..=255).chain(0..=255).collect::<Vec<u8>>();
+		let mut context = TestContext::new(memory.clone());
+
+		assert_eq!(<[u8; 1]>::from_ffi_value(&mut context, 7).unwrap(), [7]);
+		assert_eq!(<[u8; 32]>::from_ffi_value(&mut context, 8).unwrap()[..], memory[8..40]);
+		assert_eq!(<[u8; 65]>::from_ffi_value(&mut context, 9).unwrap()[..], memory[9..74]);
+		assert_eq!(<[u8; 256]>::from_ffi_value(&mut context, 10).unwrap()[..], memory[10..266]);
+		assert_eq!(context.copied.get(), 1 + 32 + 65 + 256);
+
+		assert!(<[u8; 65]>::into_preallocated_ffi_value([1; 65], &mut context, 100).is_ok());
+		assert_eq!(&context.memory[100..165], &[1; 65][..]);
+		assert_eq!(context.memory[165], 165);
+	}
+
+	#[test]
+	fn codec_array_with_wrong_length_should_error() {
+		let mut context = TestContext::new([1u16, 2, 3, 4].encode());
+
+		assert_eq!(
+			<[u16; 3]>::from_ffi_value(&mut context, pack_ptr_and_len(0, 6)),
+			Ok([1, 2, 3]),
+		);
+		assert_eq!(
+			<[u16; 3]>::from_ffi_value(&mut context, pack_ptr_and_len(0, 4)),
+			Err("Invalid length of encoded `[u16; 3]` (given 4 vs expected 6)!".into()),
+		);
+		assert_eq!(
+			<[u16; 3]>::from_ffi_value(&mut context, pack_ptr_and_len(0, 8)),
+			Err("Invalid length of encoded `[u16; 3]` (given 8 vs expected 6)!".into()),
+		);
+	}
+
+	#[test]
+	fn malformed_vec_should_error() {
+		// The length prefix announces 4 elements, but only one follows.
+		let mut context = TestContext::new(vec![16, 1, 0]);
+
+		assert_eq!(
+			<Vec<u16>>::from_ffi_value(&mut context, pack_ptr_and_len(0, 3)),
+			Err("Could not decode value from wasm: Not enough data to fill buffer".into()),
+		);
+	}
+}
@@ -74,6 +74,9 @@
 
 extern crate self as sp_runtime_interface;
 
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
 #[doc(hidden)]
 #[cfg(feature = "std")]
 pub use sp_wasm_interface;
@@ -214,6 +217,74 @@ pub use sp_std;
 /// representation. On the host each argument is converted back to the native representation and
 /// the native implementation is called. Any return value is handled in the same way.
 ///
+/// # Versioning
+///
+/// Every function exported to the host has a version, which is part of the name of the exported
+/// function. By default a function has version `1`. To change the signature or the behavior of a
+/// function without breaking already existing wasm runtimes, a new version of the function can be
+/// declared with the `#[version(N)]` attribute:
+///
+/// ```
+/// # use sp_runtime_interface::runtime_interface;
+///
+/// #[runtime_interface]
+/// trait Interface {
+///     fn compute(data: u32) -> u32 {
+///         data
+///     }
+///
+///     #[version(2)]
+///     fn compute(data: u32) -> u32 {
+///         data * 2
+///     }
+/// }
+/// ```
+///
+/// The host provides all versions of a function, `ext_interface_compute_version_1` and
+/// `ext_interface_compute_version_2` in the example above. The latest version is callable by the
+/// name of the function (`compute`), while older versions are callable by the name of the function
+/// with the version appended (`compute_version_1`).
+///
+/// # Renaming
+///
+/// A function can be renamed without breaking already existing wasm runtimes, that import it by
+/// its former name, with the `#[renamed_from("NAME")]` attribute:
+///
+/// ```
+/// # use sp_runtime_interface::runtime_interface;
+///
+/// #[runtime_interface]
+/// trait Interface {
+///     #[renamed_from("compute")]
+///     fn calculate(data: u32) -> u32 {
+///         data
+///     }
+/// }
+/// ```
+///
+/// The host provides the function as `ext_interface_calculate_version_1` and as
+/// `ext_interface_compute_version_1` in the example above. Both call the same implementation.
+///
+/// # Pure functions
+///
+/// A function whose result only depends on its arguments and that has no side effects, like a
+/// hash function, can be declared as pure with the `#[pure]` attribute:
+///
+/// ```
+/// # use sp_runtime_interface::runtime_interface;
+///
+/// #[runtime_interface]
+/// trait Interface {
+///     #[pure]
+///     fn double(data: u32) -> u32 {
+///         data * 2
+///     }
+/// }
+/// ```
+///
+/// The host function reports this through `Function::is_pure` (from `sp-wasm-interface`), so an
+/// executor may cache its results. A pure function can not take `self` or `&mut` arguments.
+///
 /// # Wasm only interfaces
 ///
 /// Some interfaces are only required from within the wasm runtime e.g. the allocator interface.
@@ -240,6 +311,9 @@ pub mod host;
 #[cfg(not(feature = "std"))]
 pub mod wasm;
 pub mod pass_by;
+pub mod compact_result;
+pub mod compact_option;
+pub mod nullable;
 
 mod util;
 
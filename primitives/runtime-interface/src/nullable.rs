@@ -0,0 +1,267 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Provides [`NullableArray`] and [`NullableBytes`], optional byte buffers that are passed as a
+//! nullable pointer instead of SCALE encoded.
+//!
+//! `Option<T>` itself is always passed SCALE encoded, see [`compact_result`](crate::compact_result)
+//! for why the nullable representation needs a wrapper.
+
+use crate::RIType;
+
+#[cfg(feature = "std")]
+use crate::host::*;
+#[cfg(not(feature = "std"))]
+use crate::wasm::*;
+
+#[cfg(feature = "std")]
+use sp_wasm_interface::FunctionContext;
+
+#[cfg(not(feature = "std"))]
+use sp_std::vec::Vec;
+
+/// A byte array `[u8; N]` that can be passed as a [`NullableArray`].
+///
+/// Byte arrays are passed as the pointer to their bytes, which is never `0`.
+pub trait ByteArray: RIType<FFIType = u32> {}
+
+/// An optional byte array `[u8; N]` that is passed as a single `u32`, without SCALE encoding.
+///
+/// `None` is passed as the pointer `0` and does not allocate. `Some` is passed like the array
+/// itself, as the pointer to its `N` bytes.
+///
+/// # Example
+///
+/// ```
+/// #[sp_runtime_interface::runtime_interface]
+/// trait Interface {
+///     fn key(seed: u8) -> sp_runtime_interface::nullable::NullableArray<[u8; 32]> {
+///         if seed > 0 { Some([seed; 32]) } else { None }.into()
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct NullableArray<T>(pub Option<T>);
+
+impl<T> From<Option<T>> for NullableArray<T> {
+	fn from(option: Option<T>) -> Self {
+		NullableArray(option)
+	}
+}
+
+impl<T> From<NullableArray<T>> for Option<T> {
+	fn from(option: NullableArray<T>) -> Self {
+		option.0
+	}
+}
+
+impl<T: ByteArray> RIType for NullableArray<T> {
+	type FFIType = u32;
+}
+
+#[cfg(not(feature = "std"))]
+impl<T: ByteArray + IntoFFIValue<Owned = ()>> IntoFFIValue for NullableArray<T> {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u32> {
+		match &self.0 {
+			Some(array) => array.into_ffi_value(),
+			None => 0.into(),
+		}
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl<T: ByteArray + FromFFIValue> FromFFIValue for NullableArray<T> {
+	fn from_ffi_value(arg: u32) -> Self {
+		NullableArray(if arg == 0 { None } else { Some(T::from_ffi_value(arg)) })
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: ByteArray + FromFFIValue<SelfInstance = T>> FromFFIValue for NullableArray<T> {
+	type SelfInstance = Self;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u32) -> Result<Self> {
+		if arg == 0 {
+			Ok(NullableArray(None))
+		} else {
+			T::from_ffi_value(context, arg).map(|array| NullableArray(Some(array)))
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: ByteArray + IntoFFIValue> IntoFFIValue for NullableArray<T> {
+	fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u32> {
+		match self.0 {
+			Some(array) => non_null(array.into_ffi_value(context)?),
+			None => Ok(0),
+		}
+	}
+}
+
+/// An optional `Vec<u8>` that is passed as a single `u64`, without SCALE encoding.
+///
+/// `None` is passed as the pointer `0` and does not allocate. `Some` is passed like the vector
+/// itself, with `length 32bit << 32 | pointer 32bit`. An empty vector has a non-zero pointer as
+/// well.
+///
+/// # Example
+///
+/// ```
+/// #[sp_runtime_interface::runtime_interface]
+/// trait Interface {
+///     fn non_empty(data: Vec<u8>) -> sp_runtime_interface::nullable::NullableBytes {
+///         if data.is_empty() { None } else { Some(data) }.into()
+///     }
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NullableBytes(pub Option<Vec<u8>>);
+
+impl From<Option<Vec<u8>>> for NullableBytes {
+	fn from(option: Option<Vec<u8>>) -> Self {
+		NullableBytes(option)
+	}
+}
+
+impl From<NullableBytes> for Option<Vec<u8>> {
+	fn from(option: NullableBytes) -> Self {
+		option.0
+	}
+}
+
+impl RIType for NullableBytes {
+	type FFIType = u64;
+}
+
+#[cfg(not(feature = "std"))]
+impl IntoFFIValue for NullableBytes {
+	type Owned = Vec<u8>;
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64, Vec<u8>> {
+		match &self.0 {
+			Some(bytes) => bytes.into_ffi_value(),
+			None => 0.into(),
+		}
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for NullableBytes {
+	fn from_ffi_value(arg: u64) -> Self {
+		let (ptr, _) = crate::unpack_ptr_and_len(arg);
+		NullableBytes(if ptr == 0 { None } else { Some(<Vec<u8>>::from_ffi_value(arg)) })
+	}
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for NullableBytes {
+	type SelfInstance = Self;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<Self> {
+		let (ptr, _) = crate::unpack_ptr_and_len(arg);
+		if ptr == 0 {
+			Ok(NullableBytes(None))
+		} else {
+			<Vec<u8>>::from_ffi_value(context, arg).map(|bytes| NullableBytes(Some(bytes)))
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for NullableBytes {
+	fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u64> {
+		match self.0 {
+			Some(bytes) => {
+				let ffi_value = bytes.into_ffi_value(context)?;
+				non_null(crate::unpack_ptr_and_len(ffi_value).0).map(|_| ffi_value)
+			}
+			None => Ok(0),
+		}
+	}
+}
+
+/// Check that memory allocated for `Some` was not given the pointer `0`, which stands for `None`.
+#[cfg(feature = "std")]
+pub(crate) fn non_null(ptr: u32) -> Result<u32> {
+	if ptr == 0 {
+		Err("Allocated memory at the null pointer".into())
+	} else {
+		Ok(ptr)
+	}
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+	use super::*;
+	use crate::host::tests::CountingContext;
+
+	/// A context whose allocator never returns the null pointer, like the wasm allocator.
+	fn context() -> CountingContext {
+		CountingContext { memory: vec![0], ..Default::default() }
+	}
+
+	#[test]
+	fn none_should_not_allocate() {
+		let mut context = context();
+
+		let array = NullableArray::<[u8; 32]>(None).into_ffi_value(&mut context).unwrap();
+		let bytes = NullableBytes(None).into_ffi_value(&mut context).unwrap();
+		assert_eq!((array, bytes), (0, 0));
+		assert_eq!(context.allocations, 0);
+
+		assert_eq!(NullableArray::<[u8; 32]>::from_ffi_value(&mut context, 0), Ok(None.into()));
+		assert_eq!(NullableBytes::from_ffi_value(&mut context, 0), Ok(None.into()));
+	}
+
+	#[test]
+	fn some_array_should_round_trip_without_scale() {
+		let mut context = context();
+
+		let ptr = NullableArray(Some([7u8; 32])).into_ffi_value(&mut context).unwrap();
+		// the array is written as it is, without any length prefix.
+		assert_eq!(context.memory.len(), 1 + 32);
+		assert_eq!(&context.memory[ptr as usize..], &[7u8; 32][..]);
+
+		let read = NullableArray::<[u8; 32]>::from_ffi_value(&mut context, ptr).unwrap();
+		assert_eq!(read, Some([7u8; 32]).into());
+	}
+
+	#[test]
+	fn some_bytes_should_round_trip_without_scale() {
+		let mut context = context();
+
+		for bytes in &[vec![1u8, 2, 3], Vec::new()] {
+			let value = NullableBytes(Some(bytes.clone()));
+			let ffi_value = value.into_ffi_value(&mut context).unwrap();
+			let (ptr, len) = crate::unpack_ptr_and_len(ffi_value);
+			assert_ne!(ptr, 0);
+			assert_eq!(&context.memory[ptr as usize..][..len as usize], &bytes[..]);
+
+			let read = NullableBytes::from_ffi_value(&mut context, ffi_value).unwrap();
+			assert_eq!(read, Some(bytes.clone()).into());
+		}
+	}
+
+	#[test]
+	fn allocation_at_the_null_pointer_should_error() {
+		let mut context = CountingContext::default();
+
+		assert!(NullableArray(Some([7u8; 4])).into_ffi_value(&mut context).is_err());
+	}
+}
@@ -20,7 +20,7 @@
 //! [`Codec`](pass_by::Codec), [`Inner`](pass_by::Inner) and [`Enum`](pass_by::Enum) are the
 //! provided strategy implementations.
 
-use crate::{RIType, util::{unpack_ptr_and_len, pack_ptr_and_len}};
+use crate::{RIType, util::unpack_ptr_and_len};
 
 #[cfg(feature = "std")]
 use crate::host::*;
@@ -28,13 +28,19 @@ use crate::host::*;
 use crate::wasm::*;
 
 #[cfg(feature = "std")]
-use sp_wasm_interface::{FunctionContext, Pointer, Result};
+use sp_wasm_interface::{FunctionContext, Pointer};
+
+#[cfg(feature = "std")]
+use crate::util::{read_memory_small, write_encoded};
 
 use sp_std::{marker::PhantomData, convert::TryFrom};
 
 #[cfg(not(feature = "std"))]
 use sp_std::{slice, vec::Vec};
 
+#[cfg(not(feature = "std"))]
+use crate::util::pack_ptr_and_len;
+
 /// Derive macro for implementing [`PassBy`] with the [`Codec`] strategy.
 ///
 /// This requires that the type implements [`Encode`](codec::Encode) and [`Decode`](codec::Decode)
@@ -76,6 +82,14 @@ pub use sp_runtime_interface_proc_macro::PassByCodec;
 ///     data: [u8; 32],
 /// }
 /// ```
+///
+/// A newtype around an integer is passed as the bare integer, without encoding or allocating:
+///
+/// ```
+/// # use sp_runtime_interface::pass_by::PassByInner;
+/// #[derive(PassByInner)]
+/// struct Weight(u64);
+/// ```
 pub use sp_runtime_interface_proc_macro::PassByInner;
 
 /// Derive macro for implementing [`PassBy`] with the [`Enum`] strategy.
@@ -153,6 +167,13 @@ pub trait PassByImpl<T>: RIType {
 	///
 	/// For more information see: [`crate::wasm::FromFFIValue::from_ffi_value`]
 	fn from_ffi_value(arg: Self::FFIType) -> T;
+
+	/// Create `T` from the given ffi value, returning an error if it can not be decoded.
+	///
+	/// For more information see: [`crate::wasm::FromFFIValue::try_from_ffi_value`]
+	fn try_from_ffi_value(arg: Self::FFIType) -> Result<T, codec::Error> {
+		Ok(Self::from_ffi_value(arg))
+	}
 }
 
 impl<T: PassBy> RIType for T {
@@ -195,6 +216,10 @@ impl<T: PassBy> FromFFIValue for T {
 	fn from_ffi_value(arg: <T::PassBy as RIType>::FFIType) -> Self {
 		T::PassBy::from_ffi_value(arg)
 	}
+
+	fn try_from_ffi_value(arg: <T::PassBy as RIType>::FFIType) -> Result<Self, codec::Error> {
+		T::PassBy::try_from_ffi_value(arg)
+	}
 }
 
 /// The implementation of the pass by codec strategy. This strategy uses a SCALE encoded
@@ -206,6 +231,12 @@ impl<T: PassBy> FromFFIValue for T {
 ///
 /// [`PassByCodec`](derive.PassByCodec.html) is a derive macro to implement this strategy.
 ///
+/// There is no blanket implementation of [`PassBy`] for all types that implement
+/// [`Codec`](codec::Codec), as it would overlap with the implementations for the other strategies
+/// and for types like `Vec<T>` that are not passed SCALE encoded. Every type, including generic
+/// wrapper types, needs to opt in with its own implementation instead. Because of the orphan
+/// rules, this is only possible in the crate that declares the type.
+///
 /// # Example
 /// ```
 /// # use sp_runtime_interface::pass_by::{PassBy, Codec};
@@ -224,11 +255,7 @@ impl<T: codec::Codec> PassByImpl<T> for Codec<T> {
 		instance: T,
 		context: &mut dyn FunctionContext,
 	) -> Result<Self::FFIType> {
-		let vec = instance.encode();
-		let ptr = context.allocate_memory(vec.len() as u32)?;
-		context.write_memory(ptr, &vec)?;
-
-		Ok(pack_ptr_and_len(ptr.into(), vec.len() as u32))
+		write_encoded(context, &instance)
 	}
 
 	fn from_ffi_value(
@@ -236,9 +263,9 @@ impl<T: codec::Codec> PassByImpl<T> for Codec<T> {
 		arg: Self::FFIType,
 	) -> Result<T> {
 		let (ptr, len) = unpack_ptr_and_len(arg);
-		let vec = context.read_memory(Pointer::new(ptr), len)?;
-		T::decode(&mut &vec[..])
-			.map_err(|e| format!("Could not decode value from wasm: {}", e.what()))
+		let data = read_memory_small(context, Pointer::new(ptr), len)?;
+		T::decode(&mut &data[..])
+			.map_err(|e| format!("Could not decode value from wasm: {}", e.what()).into())
 	}
 }
 
@@ -253,11 +280,15 @@ impl<T: codec::Codec> PassByImpl<T> for Codec<T> {
 	}
 
 	fn from_ffi_value(arg: Self::FFIType) -> T {
+		Self::try_from_ffi_value(arg).expect("Host to wasm values are encoded correctly; qed")
+	}
+
+	fn try_from_ffi_value(arg: Self::FFIType) -> Result<T, codec::Error> {
 		let (ptr, len) = unpack_ptr_and_len(arg);
 		let len = len as usize;
 
 		let slice = unsafe { slice::from_raw_parts(ptr as *const u8, len) };
-		T::decode(&mut &slice[..]).expect("Host to wasm values are encoded correctly; qed")
+		T::decode(&mut &slice[..])
 	}
 }
 
@@ -421,7 +452,7 @@ impl<T: Copy + Into<u8> + TryFrom<u8>> PassByImpl<T> for Enum<T> {
 		_: &mut dyn FunctionContext,
 		arg: Self::FFIType,
 	) -> Result<T> {
-		T::try_from(arg).map_err(|_| format!("Invalid enum discriminant: {}", arg))
+		T::try_from(arg).map_err(|_| format!("Invalid enum discriminant: {}", arg).into())
 	}
 }
 
@@ -16,7 +16,19 @@
 
 //! Various utilities that help interfacing with wasm runtime code.
 
+#[cfg(feature = "std")]
+use codec::{Encode, Output};
+
+#[cfg(feature = "std")]
+use sp_wasm_interface::{FunctionContext, Pointer};
+
+#[cfg(feature = "std")]
+use crate::host::Result;
+
 /// Pack a pointer and length into an `u64`.
+///
+/// The `u64` is passed as a wasm value and not through memory, so its layout does not depend on
+/// the byte order of the host.
 pub fn pack_ptr_and_len(ptr: u32, len: u32) -> u64 {
 	// The static assertions from above are changed into a runtime check.
 	#[cfg(all(not(feature = "std"), feature = "disable_target_static_assertions"))]
@@ -41,9 +53,185 @@ pub fn unpack_ptr_and_len(val: u64) -> (u32, u32) {
 	(ptr, len)
 }
 
+/// Unpacks an `u64` into the pointer and length and checks that the region fits into a memory of
+/// `mem_size` bytes.
+#[cfg(feature = "std")]
+pub fn checked_pointer_and_len(val: u64, mem_size: u32) -> Result<(u32, u32)> {
+	let (ptr, len) = unpack_ptr_and_len(val);
+
+	match ptr.checked_add(len) {
+		Some(end) if end <= mem_size => Ok((ptr, len)),
+		_ => Err("pointer+length out of bounds".into()),
+	}
+}
+
+/// Returns the length of the SCALE encoding of `value` without allocating the encoded data.
+#[cfg(feature = "std")]
+pub fn encoded_len<T: Encode + ?Sized>(value: &T) -> usize {
+	struct Counter(usize);
+
+	impl Output for Counter {
+		fn write(&mut self, bytes: &[u8]) {
+			self.0 += bytes.len();
+		}
+	}
+
+	let mut counter = Counter(0);
+	value.encode_to(&mut counter);
+	counter.0
+}
+
+/// The buffer returned by [`read_memory_small`].
+///
+/// With the `smallvec` feature, reads of up to 64 bytes are kept inline instead of on the heap.
+#[cfg(all(feature = "std", feature = "smallvec"))]
+pub type SmallRead = smallvec::SmallVec<[u8; 64]>;
+
+/// The buffer returned by [`read_memory_small`].
+#[cfg(all(feature = "std", not(feature = "smallvec")))]
+pub type SmallRead = sp_std::vec::Vec<u8>;
+
+/// Read `len` bytes from the wasm memory at `ptr` into a buffer that is only used temporarily,
+/// like for decoding the data.
+///
+/// With the `smallvec` feature, small reads do not allocate. Use `read_memory` if the data is
+/// kept anyway.
+#[cfg(feature = "std")]
+pub fn read_memory_small(
+	context: &dyn FunctionContext,
+	ptr: Pointer<u8>,
+	len: u32,
+) -> Result<SmallRead> {
+	#[cfg(feature = "smallvec")]
+	let data = {
+		let mut data = SmallRead::from_elem(0, len as usize);
+		context.read_memory_into(ptr, &mut data[..])?;
+		data
+	};
+	#[cfg(not(feature = "smallvec"))]
+	let data = context.read_memory(ptr, len)?;
+
+	Ok(data)
+}
+
+/// The number of bytes that [`MemoryOutput`] collects before writing them into the wasm memory.
+#[cfg(feature = "std")]
+const MEMORY_OUTPUT_BUFFER_SIZE: usize = 1024;
+
+/// An [`Output`] that writes SCALE encoded data directly into the wasm memory.
+///
+/// The encoder writes each field apart, so small writes are collected in a fixed buffer on the
+/// stack and written with a single call of the [`FunctionContext`]. Writes that do not fit into
+/// the buffer go to the wasm memory directly.
+///
+/// The first error returned by the [`FunctionContext`] is kept and all following writes are
+/// ignored. Use [`MemoryOutput::finish`] to write the remaining buffered bytes and to get the
+/// result.
+#[cfg(feature = "std")]
+pub struct MemoryOutput<'a> {
+	context: &'a mut dyn FunctionContext,
+	ptr: Pointer<u8>,
+	offset: u32,
+	buffer: [u8; MEMORY_OUTPUT_BUFFER_SIZE],
+	buffered: usize,
+	result: Result<()>,
+}
+
+#[cfg(feature = "std")]
+impl<'a> MemoryOutput<'a> {
+	/// Create a new instance that starts writing at `ptr`.
+	pub fn new(context: &'a mut dyn FunctionContext, ptr: Pointer<u8>) -> Self {
+		Self {
+			context,
+			ptr,
+			offset: 0,
+			buffer: [0; MEMORY_OUTPUT_BUFFER_SIZE],
+			buffered: 0,
+			result: Ok(()),
+		}
+	}
+
+	/// Finish writing and return the result of all writes.
+	pub fn finish(mut self) -> Result<()> {
+		self.flush();
+		self.result
+	}
+
+	/// Write the buffered bytes into the wasm memory.
+	fn flush(&mut self) {
+		let buffered = sp_std::mem::replace(&mut self.buffered, 0);
+		if buffered > 0 {
+			let Self { context, ptr, offset, buffer, result, .. } = self;
+			write_at(&mut **context, *ptr, offset, &buffer[..buffered], result);
+		}
+	}
+}
+
+/// Write `bytes` at `offset` from `ptr` and advance `offset`, unless `result` is an error already.
+#[cfg(feature = "std")]
+fn write_at(
+	context: &mut dyn FunctionContext,
+	ptr: Pointer<u8>,
+	offset: &mut u32,
+	bytes: &[u8],
+	result: &mut Result<()>,
+) {
+	if result.is_err() {
+		return
+	}
+
+	*result = match ptr.offset(*offset) {
+		Some(ptr) => context.write_memory(ptr, bytes).map_err(Into::into),
+		None => Err("Pointer overflow while writing encoded data".into()),
+	};
+	*offset += bytes.len() as u32;
+}
+
+/// Encode `value` into a new buffer in the wasm memory and return its pointer and length packed
+/// by [`pack_ptr_and_len`].
+///
+/// The buffer is allocated with the length returned by [`encoded_len`] and the encoded data is
+/// written into it by a [`MemoryOutput`], so it is never allocated on the host. This matters for
+/// values of several megabytes, like storage values.
+#[cfg(feature = "std")]
+pub fn write_encoded<T: Encode + ?Sized>(
+	context: &mut dyn FunctionContext,
+	value: &T,
+) -> Result<u64> {
+	let len = encoded_len(value) as u32;
+	let ptr = context.allocate_memory(len)?;
+
+	let mut output = MemoryOutput::new(context, ptr);
+	value.encode_to(&mut output);
+	output.finish()?;
+
+	Ok(pack_ptr_and_len(ptr.into(), len))
+}
+
+#[cfg(feature = "std")]
+impl<'a> Output for MemoryOutput<'a> {
+	fn write(&mut self, bytes: &[u8]) {
+		if self.buffered + bytes.len() > MEMORY_OUTPUT_BUFFER_SIZE {
+			self.flush();
+		}
+
+		if bytes.len() >= MEMORY_OUTPUT_BUFFER_SIZE {
+			write_at(&mut *self.context, self.ptr, &mut self.offset, bytes, &mut self.result);
+		} else {
+			self.buffer[self.buffered..self.buffered + bytes.len()].copy_from_slice(bytes);
+			self.buffered += bytes.len();
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use super::{pack_ptr_and_len, unpack_ptr_and_len};
+	use super::{
+		pack_ptr_and_len, unpack_ptr_and_len, encoded_len, checked_pointer_and_len,
+		read_memory_small, write_encoded,
+	};
+	use codec::Encode;
+	use sp_wasm_interface::Pointer;
 
 	#[test]
 	fn ptr_len_packing_unpacking() {
@@ -56,4 +244,104 @@ mod tests {
 		assert_eq!(PTR, ptr);
 		assert_eq!(LEN, len);
 	}
+
+	/// Deterministic `xorshift64` generator to spread test values over the full `u32` range.
+	fn next_random(state: &mut u64) -> u32 {
+		*state ^= *state << 13;
+		*state ^= *state >> 7;
+		*state ^= *state << 17;
+		(*state >> 16) as u32
+	}
+
+	#[test]
+	fn ptr_len_packing_round_trips_over_full_range() {
+		let edges = [0, 1, 0x7fff_ffff, 0x8000_0000, u32::max_value() - 1, u32::max_value()];
+		let mut state = 0x5eed_1234_dead_beef;
+		let random = (0..10_000).map(|_| (next_random(&mut state), next_random(&mut state)));
+
+		let cases = edges.iter().flat_map(|p| edges.iter().map(move |l| (*p, *l))).chain(random);
+		for (ptr, len) in cases {
+			let packed = pack_ptr_and_len(ptr, len);
+			assert_eq!(unpack_ptr_and_len(packed), (ptr, len));
+
+			// The layout must not depend on the endianness of the host.
+			let mut expected = [0u8; 8];
+			expected[..4].copy_from_slice(&ptr.to_le_bytes());
+			expected[4..].copy_from_slice(&len.to_le_bytes());
+			assert_eq!(packed.to_le_bytes(), expected);
+			assert_eq!(unpack_ptr_and_len(u64::from_le_bytes(expected)), (ptr, len));
+		}
+	}
+
+	#[test]
+	fn encoded_len_matches_encode() {
+		let data = (0..1000u32).collect::<Vec<_>>();
+		assert_eq!(encoded_len(&data), data.encode().len());
+
+		let data = Some((1u8, vec![1u64, 2, 3]));
+		assert_eq!(encoded_len(&data), data.encode().len());
+	}
+
+	#[test]
+	fn checked_pointer_and_len_rejects_out_of_bounds() {
+		let err = Err("pointer+length out of bounds".into());
+
+		assert_eq!(checked_pointer_and_len(pack_ptr_and_len(u32::max_value(), 1), 1024), err);
+		assert_eq!(checked_pointer_and_len(pack_ptr_and_len(0, 1025), 1024), err);
+		assert_eq!(checked_pointer_and_len(pack_ptr_and_len(1000, 25), 1024), err);
+
+		assert_eq!(checked_pointer_and_len(pack_ptr_and_len(1000, 24), 1024), Ok((1000, 24)));
+		assert_eq!(checked_pointer_and_len(pack_ptr_and_len(1024, 0), 1024), Ok((1024, 0)));
+	}
+
+	#[test]
+	fn small_reads_should_return_the_read_bytes() {
+		let memory = (0..200u8).collect::<Vec<_>>();
+		let context = crate::impls::tests::TestContext::new(memory);
+
+		let small = read_memory_small(&context, Pointer::new(10), 32).unwrap();
+		assert_eq!(&small[..], &(10..42).collect::<Vec<u8>>()[..]);
+		let large = read_memory_small(&context, Pointer::new(100), 100).unwrap();
+		assert_eq!(&large[..], &(100..200).collect::<Vec<u8>>()[..]);
+
+		#[cfg(feature = "smallvec")]
+		{
+			assert!(!small.spilled());
+			assert!(large.spilled());
+		}
+	}
+
+	#[test]
+	fn write_encoded_should_match_encode() {
+		let mut context = crate::host::tests::CountingContext::default();
+
+		// A storage value of 4 MiB, as returned by `storage::get`.
+		let value = Some(vec![0xabu8; 4 * 1024 * 1024]);
+		let (ptr, len) = unpack_ptr_and_len(write_encoded(&mut context, &value).unwrap());
+		assert_eq!(context.allocations, 1);
+		assert_eq!(&context.memory[ptr as usize..(ptr + len) as usize], &value.encode()[..]);
+
+		let value = (0..1_000_000u32).collect::<Vec<_>>();
+		let (ptr, len) = unpack_ptr_and_len(write_encoded(&mut context, &value).unwrap());
+		assert_eq!(context.allocations, 2);
+		assert_eq!(&context.memory[ptr as usize..(ptr + len) as usize], &value.encode()[..]);
+	}
+
+	#[test]
+	fn write_encoded_should_buffer_small_writes() {
+		let mut context = crate::host::tests::CountingContext::default();
+
+		// Each element is a separate write of the encoder.
+		let value = (0..10_000u32).collect::<Vec<_>>();
+		let (ptr, len) = unpack_ptr_and_len(write_encoded(&mut context, &value).unwrap());
+		assert_eq!(&context.memory[ptr as usize..(ptr + len) as usize], &value.encode()[..]);
+		assert_eq!(context.writes, (len as usize + 1023) / 1024);
+
+		// Large writes are not copied into the buffer, buffered bytes are written before them.
+		context.writes = 0;
+		let value = (1u8, vec![7u8; 4096], 2u8);
+		let (ptr, len) = unpack_ptr_and_len(write_encoded(&mut context, &value).unwrap());
+		assert_eq!(&context.memory[ptr as usize..(ptr + len) as usize], &value.encode()[..]);
+		assert_eq!(context.writes, 3);
+	}
 }
@@ -30,6 +30,15 @@ use sp_std::cell::Cell;
 pub trait FromFFIValue: Sized + RIType {
 	/// Create `Self` from the given ffi value.
 	fn from_ffi_value(arg: Self::FFIType) -> Self;
+
+	/// Create `Self` from the given ffi value, returning an error instead of panicking if the
+	/// value can not be decoded.
+	///
+	/// Meant for values that carry data from outside of the runtime, like off-chain HTTP
+	/// responses. By default this forwards to [`FromFFIValue::from_ffi_value`].
+	fn try_from_ffi_value(arg: Self::FFIType) -> Result<Self, codec::Error> {
+		Ok(Self::from_ffi_value(arg))
+	}
 }
 
 /// Something that can be converted into a ffi value.
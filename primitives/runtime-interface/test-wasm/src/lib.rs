@@ -18,17 +18,85 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use sp_runtime_interface::runtime_interface;
+use sp_runtime_interface::{
+	RIType, runtime_interface, compact_result::CompactResult,
+	compact_option::CompactOption, pass_by::{PassBy, PassByInner, Codec},
+};
+
+use codec::{Encode, Decode, Compact};
+
+use sp_std::{
+	num::NonZeroU32, marker::PhantomData, collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+	borrow::Cow,
+};
+
+use core::{time::Duration, ops::{Range, RangeInclusive}};
 
 #[cfg(not(feature = "std"))]
-use sp_std::{vec, vec::Vec, mem, convert::TryFrom};
+use sp_std::{vec, vec::Vec, mem, convert::TryFrom, boxed::Box};
 
-use sp_core::{sr25519::Public, wasm_export_functions};
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+use sp_core::{sr25519::Public, ecdsa, H160, H256, wasm_export_functions};
 
 // Inlucde the WASM binary
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
+/// Bytes that a host function can return without copying them.
+static STATIC_BYTES: [u8; 6] = *b"static";
+
+/// A custom type that is passed SCALE encoded, without using the derive.
+#[derive(Encode, Decode, PartialEq, Clone)]
+pub struct EncodedType<T> {
+	name: Vec<u8>,
+	values: Vec<T>,
+	flag: Option<bool>,
+}
+
+impl<T: Encode + Decode> PassBy for EncodedType<T> {
+	type PassBy = Codec<Self>;
+}
+
+/// A newtype around an array that is passed as its inner array, using the derive.
+#[derive(PassByInner, PartialEq, Clone, Copy)]
+pub struct AccountId20([u8; 20]);
+
+/// A newtype around an integer that is passed as its inner integer, using the derive.
+#[derive(PassByInner, PartialEq, Clone, Copy)]
+pub struct Weight(u64);
+
+/// A type whose host side conversion panics.
+pub struct PanicOnConversion;
+
+impl RIType for PanicOnConversion {
+	type FFIType = u32;
+}
+
+#[cfg(not(feature = "std"))]
+impl sp_runtime_interface::wasm::IntoFFIValue for PanicOnConversion {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> sp_runtime_interface::wasm::WrappedFFIValue<u32> {
+		0.into()
+	}
+}
+
+#[cfg(feature = "std")]
+impl sp_runtime_interface::host::FromFFIValue for PanicOnConversion {
+	type SelfInstance = Self;
+
+	fn from_ffi_value(
+		_: &mut dyn sp_runtime_interface::sp_wasm_interface::FunctionContext,
+		_: u32,
+	) -> sp_runtime_interface::host::Result<Self> {
+		panic!("The conversion always panics")
+	}
+}
+
 /// Used in the `test_array_as_mutable_reference` test.
 const TEST_ARRAY: [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
 
@@ -39,6 +107,11 @@ pub trait TestApi {
 		data
 	}
 
+	/// Returns the input data as result.
+	fn return_input_encoded(data: Vec<u32>) -> Vec<u32> {
+		data
+	}
+
 	/// Set the storage at key with value.
 	fn set_storage(&mut self, key: &[u8], data: &[u8]) {
 		self.place_storage(key.to_vec(), Some(data.to_vec()));
@@ -62,6 +135,19 @@ pub trait TestApi {
 		res
 	}
 
+	/// Get a large array as input and returns a subset of this array.
+	fn get_and_return_large_array(data: [u8; 96]) -> [u8; 256] {
+		let mut res = [0u8; 256];
+		res[..96].copy_from_slice(&data);
+		res
+	}
+
+	/// Copy `hello` into the given mutable vector, without changing its length.
+	fn return_value_into_mutable_vec(data: &mut Vec<u8>) {
+		let res = "hello";
+		data[..res.as_bytes().len()].copy_from_slice(res.as_bytes());
+	}
+
 	/// Take and fill mutable array.
 	fn array_as_mutable_reference(data: &mut [u8; 16]) {
 		data.copy_from_slice(&TEST_ARRAY);
@@ -72,6 +158,238 @@ pub trait TestApi {
 		key
 	}
 
+	/// Returns the input `u128` as result.
+	fn return_input_u128(data: u128) -> u128 {
+		data
+	}
+
+	/// Returns the input `i128` as result.
+	fn return_input_i128(data: i128) -> i128 {
+		data
+	}
+
+	/// Returns the input tuple as result.
+	fn return_input_tuple(data: (u8, u32, u64)) -> (u8, u32, u64) {
+		data
+	}
+
+	/// Returns the input one element tuple as result.
+	fn return_input_single_tuple(data: (Vec<u8>,)) -> (Vec<u8>,) {
+		data
+	}
+
+	/// Returns the input string with `!` appended.
+	fn return_input_string(data: String) -> String {
+		data + "!"
+	}
+
+	/// Returns the length of the given string.
+	fn str_len(data: &str) -> u32 {
+		data.len() as u32
+	}
+
+	/// Returns the given hashes as result.
+	fn return_input_hashes(a: H160, b: H256) -> Vec<u8> {
+		let mut res = a.as_bytes().to_vec();
+		res.extend_from_slice(b.as_bytes());
+		res
+	}
+
+	/// Returns the given `H256` as result.
+	fn return_input_h256(hash: H256) -> H256 {
+		hash
+	}
+
+	/// Returns the input `f32` as result.
+	fn return_input_f32(data: f32) -> f32 {
+		data
+	}
+
+	/// Returns the input `f64` as result.
+	fn return_input_f64(data: f64) -> f64 {
+		data
+	}
+
+	/// Returns the input `char` as result.
+	fn return_input_char(data: char) -> char {
+		data
+	}
+
+	/// A function that is called with an invalid `char` from the runtime.
+	fn invalid_char(_: char) {}
+
+	/// A function whose argument panics when it is converted on the host.
+	fn panic_on_conversion(_data: PanicOnConversion) {}
+
+	/// Returns the input `[u16; 3]` as result.
+	fn return_input_u16_array(data: [u16; 3]) -> [u16; 3] {
+		data
+	}
+
+	/// Returns the input `NonZeroU32` as result.
+	fn return_input_non_zero_u32(data: NonZeroU32) -> NonZeroU32 {
+		data
+	}
+
+	/// Returns the input `Compact<u32>` as result.
+	fn return_input_compact_u32(data: Compact<u32>) -> Compact<u32> {
+		data
+	}
+
+	/// Returns the input `Compact<u64>` as result.
+	fn return_input_compact_u64(data: Compact<u64>) -> Compact<u64> {
+		data
+	}
+
+	/// Returns the input `Duration` as result.
+	fn return_input_duration(data: Duration) -> Duration {
+		data
+	}
+
+	/// Returns the input `PhantomData` as result.
+	fn return_input_phantom_data(data: PhantomData<u32>) -> PhantomData<u32> {
+		data
+	}
+
+	/// Returns the input `value` as result, ignoring the `PhantomData` before it.
+	fn return_value_after_phantom_data(_marker: PhantomData<u32>, value: u32) -> u32 {
+		value
+	}
+
+	/// Returns the input vector of blobs as result.
+	fn return_input_vec_of_vecs(data: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+		data
+	}
+
+	/// Returns the input slice of hashes as result.
+	fn return_input_hash_slice(data: &[[u8; 32]]) -> Vec<[u8; 32]> {
+		data.to_vec()
+	}
+
+	/// Returns the input `()` as result.
+	#[allow(clippy::unused_unit, clippy::unit_arg)]
+	fn return_input_unit(data: ()) -> () {
+		data
+	}
+
+	/// Returns the input `value` as result, ignoring the `()` before it.
+	#[allow(clippy::unit_arg)]
+	fn return_value_after_unit(_unit: (), value: u32) -> u32 {
+		value
+	}
+
+	/// Returns the input boxed slice as result.
+	fn return_input_boxed_slice(data: Box<[u8]>) -> Box<[u8]> {
+		data
+	}
+
+	/// Returns the input `BTreeMap` as result.
+	fn return_input_btree_map(data: BTreeMap<u32, Vec<u8>>) -> BTreeMap<u32, Vec<u8>> {
+		data
+	}
+
+	/// Returns the input `BTreeSet` as result.
+	fn return_input_btree_set(data: BTreeSet<u64>) -> BTreeSet<u64> {
+		data
+	}
+
+	/// Returns the input ecdsa public key as result.
+	fn return_input_ecdsa_public(data: ecdsa::Public) -> ecdsa::Public {
+		data
+	}
+
+	/// Returns the input ecdsa signature as result.
+	fn return_input_ecdsa_signature(data: ecdsa::Signature) -> ecdsa::Signature {
+		data
+	}
+
+	/// Returns the input `EncodedType` as result.
+	fn return_input_encoded_type(data: EncodedType<u16>) -> EncodedType<u16> {
+		data
+	}
+
+	/// Returns the input `AccountId20` as result.
+	fn return_input_account_id_20(data: AccountId20) -> AccountId20 {
+		data
+	}
+
+	/// Returns the input `Weight` as result.
+	fn return_input_weight(data: Weight) -> Weight {
+		data
+	}
+
+	/// Returns the input `CompactResult` as result.
+	fn return_input_compact_result(data: CompactResult<(), u8>) -> CompactResult<(), u8> {
+		data
+	}
+
+	/// Returns the input `CompactResult` of signed values as result.
+	fn return_input_signed_compact_result(data: CompactResult<i16, i8>) -> CompactResult<i16, i8> {
+		data
+	}
+
+	/// Returns the input `CompactOption` as result.
+	fn return_input_compact_option(data: CompactOption<u32>) -> CompactOption<u32> {
+		data
+	}
+
+	/// Returns the input `CompactOption` of a `bool` as result.
+	fn return_input_bool_compact_option(data: CompactOption<bool>) -> CompactOption<bool> {
+		data
+	}
+
+	/// Returns the input `CompactOption` of a signed value as result.
+	fn return_input_signed_compact_option(data: CompactOption<i32>) -> CompactOption<i32> {
+		data
+	}
+
+	/// Returns the input `CompactOption` of a `u64`, which is passed as a pointer, as result.
+	fn return_input_u64_compact_option(data: CompactOption<u64>) -> CompactOption<u64> {
+		data
+	}
+
+	/// Returns the input `Range` as result.
+	fn return_input_range(data: Range<u32>) -> Range<u32> {
+		data
+	}
+
+	/// Returns the input `RangeInclusive` as result.
+	fn return_input_range_inclusive(data: RangeInclusive<u32>) -> RangeInclusive<u32> {
+		data
+	}
+
+	/// Returns static bytes if `borrowed`, otherwise the same bytes as owned vector.
+	fn return_static_or_owned_bytes(borrowed: bool) -> Cow<'static, [u8]> {
+		if borrowed {
+			Cow::Borrowed(&STATIC_BYTES[..])
+		} else {
+			Cow::Owned(STATIC_BYTES.to_vec())
+		}
+	}
+
+	/// The first version of a versioned function.
+	fn versioned_function(data: u32) -> u32 {
+		data
+	}
+
+	/// The second version of a versioned function.
+	#[version(2)]
+	fn versioned_function(data: u32) -> u32 {
+		data + 1
+	}
+
+	/// A function that was renamed, so the host also provides it as `old_function_name`.
+	#[renamed_from("old_function_name")]
+	fn renamed_function(data: u32) -> u32 {
+		data + 2
+	}
+
+	/// A function that is declared as pure.
+	#[pure]
+	fn pure_function(data: u32) -> u32 {
+		data + 3
+	}
+
 	/// A function that is called with invalid utf8 data from the runtime.
 	///
 	/// This also checks that we accept `_` (wild card) argument names.
@@ -114,6 +432,13 @@ wasm_export_functions! {
 		assert_eq!(input, res);
 	}
 
+	fn test_return_encoded_data() {
+		let input = (0..10_000u32).collect::<Vec<_>>();
+		let res = test_api::return_input_encoded(input.clone());
+
+		assert_eq!(input, res);
+	}
+
 	fn test_return_option_data() {
 		let input = vec![1, 2, 3, 4, 5, 6];
 		let res = test_api::return_option_input(input.clone());
@@ -137,6 +462,14 @@ wasm_export_functions! {
 		assert_eq!(expected.as_bytes(), &data[..expected.len()]);
 	}
 
+	fn test_return_value_into_mutable_vec() {
+		let mut data = vec![1, 2, 3, 4, 5, 6];
+
+		test_api::return_value_into_mutable_vec(&mut data);
+
+		assert_eq!(data, b"hello\x06".to_vec());
+	}
+
 	fn test_get_and_return_array() {
 		let mut input = unsafe { mem::MaybeUninit::<[u8; 34]>::zeroed().assume_init() };
 		input.copy_from_slice(&[
@@ -149,6 +482,30 @@ wasm_export_functions! {
 		assert_eq!(&res, &input[..16]);
 	}
 
+	fn test_get_and_return_large_array() {
+		let mut input = [0u8; 96];
+		input.iter_mut().enumerate().for_each(|(i, v)| *v = i as u8);
+
+		let res = test_api::get_and_return_large_array(input);
+
+		assert_eq!(&res[..96], &input[..]);
+		assert!(res[96..].iter().all(|v| *v == 0));
+	}
+
+	fn test_get_and_return_array_frees_memory() {
+		let mut input = [0u8; 34];
+		for i in 0..1000u32 {
+			input[0] = i as u8;
+
+			// Interleave with some other allocations.
+			let other = vec![i; 10];
+			let res = test_api::get_and_return_array(input);
+
+			assert_eq!(&res, &input[..16]);
+			assert_eq!(other, vec![i; 10]);
+		}
+	}
+
 	fn test_array_as_mutable_reference() {
 		let mut array = [0u8; 16];
 		test_api::array_as_mutable_reference(&mut array);
@@ -170,6 +527,247 @@ wasm_export_functions! {
 		assert_eq!(key_data, ret_key_data);
 	}
 
+	fn test_return_input_wide_integers() {
+		for input in &[0, 1, u64::max_value() as u128 + 1, u128::max_value()] {
+			assert_eq!(test_api::return_input_u128(*input), *input);
+		}
+
+		for input in &[0, -1, i64::min_value() as i128 - 1, i128::min_value(), i128::max_value()] {
+			assert_eq!(test_api::return_input_i128(*input), *input);
+		}
+	}
+
+	fn test_return_input_tuples() {
+		let input = (u8::max_value(), 1, u64::max_value());
+		assert_eq!(test_api::return_input_tuple(input), input);
+
+		let input = (vec![1, 2, 3, 4, 5, 6],);
+		assert_eq!(test_api::return_input_single_tuple(input.clone()), input);
+	}
+
+	fn test_return_input_string() {
+		let input = String::from("hello wörld");
+		assert_eq!(test_api::return_input_string(input.clone()), input + "!");
+		assert_eq!(test_api::str_len("hello wörld"), 12);
+	}
+
+	fn test_return_input_hashes() {
+		let a = H160::repeat_byte(0x11);
+		let mut b = H256::zero();
+		b.as_bytes_mut().iter_mut().enumerate().for_each(|(i, v)| *v = i as u8);
+
+		let res = test_api::return_input_hashes(a, b);
+		assert_eq!(&res[..20], a.as_bytes());
+		assert_eq!(&res[20..], b.as_bytes());
+
+		assert_eq!(test_api::return_input_h256(b), b);
+	}
+
+	fn test_return_input_floats() {
+		let nan_with_payload = f32::from_bits(0x7fc0_1234);
+		for input in &[core::f32::NAN, nan_with_payload, -0.0, core::f32::INFINITY, 1.5] {
+			assert_eq!(test_api::return_input_f32(*input).to_bits(), input.to_bits());
+		}
+
+		for input in &[core::f64::NAN, -0.0, core::f64::INFINITY, core::f64::NEG_INFINITY, 1.5] {
+			assert_eq!(test_api::return_input_f64(*input).to_bits(), input.to_bits());
+		}
+	}
+
+	fn test_return_input_char() {
+		for input in &['a', 'ö', '\u{10FFFF}'] {
+			assert_eq!(test_api::return_input_char(*input), *input);
+		}
+	}
+
+	fn test_invalid_char_should_return_an_error() {
+		// A surrogate code point is not a valid `char`.
+		let data = unsafe { core::char::from_u32_unchecked(0xD800) };
+
+		test_api::invalid_char(data);
+	}
+
+	fn test_panicking_conversion_should_return_an_error() {
+		test_api::panic_on_conversion(PanicOnConversion);
+	}
+
+	fn test_return_input_u16_array() {
+		let input = [1, 0x1234, u16::max_value()];
+		assert_eq!(test_api::return_input_u16_array(input), input);
+	}
+
+	fn test_return_input_non_zero_u32() {
+		for input in &[1, 0x1234, u32::max_value()] {
+			let input = NonZeroU32::new(*input).unwrap();
+			assert_eq!(test_api::return_input_non_zero_u32(input), input);
+		}
+	}
+
+	fn test_return_input_compact() {
+		for input in &[0, 63, 64, 16383, 16384, u32::max_value()] {
+			assert_eq!(test_api::return_input_compact_u32(Compact(*input)).0, *input);
+			let input = *input as u64;
+			assert_eq!(test_api::return_input_compact_u64(Compact(input)).0, input);
+		}
+		let input = u64::max_value();
+		assert_eq!(test_api::return_input_compact_u64(Compact(input)).0, input);
+	}
+
+	fn test_return_input_duration() {
+		for millis in &[0, 250, 3 * 24 * 60 * 60 * 1000, u64::max_value()] {
+			let input = Duration::from_millis(*millis);
+			assert_eq!(test_api::return_input_duration(input), input);
+		}
+	}
+
+	fn test_return_input_phantom_data() {
+		assert_eq!(test_api::return_input_phantom_data(PhantomData), PhantomData);
+		assert_eq!(test_api::return_value_after_phantom_data(PhantomData, 0x1234), 0x1234);
+	}
+
+	fn test_return_input_vec_of_vecs() {
+		let input = (0..100u8).map(|i| vec![i; i as usize % 8]).collect::<Vec<_>>();
+		assert_eq!(test_api::return_input_vec_of_vecs(input.clone()), input);
+		assert_eq!(test_api::return_input_vec_of_vecs(vec![Vec::new(); 3]), vec![Vec::new(); 3]);
+		assert!(test_api::return_input_vec_of_vecs(Vec::new()).is_empty());
+	}
+
+	fn test_return_input_hash_slice() {
+		let input = [[1u8; 32], [2; 32], [3; 32]];
+		assert_eq!(test_api::return_input_hash_slice(&input), input.to_vec());
+		assert!(test_api::return_input_hash_slice(&[]).is_empty());
+	}
+
+	fn test_return_input_unit() {
+		assert_eq!(test_api::return_input_unit(()), ());
+		assert_eq!(test_api::return_value_after_unit((), 0x1234), 0x1234);
+	}
+
+	fn test_return_input_boxed_slice() {
+		let input = (0..100u8).collect::<Vec<_>>().into_boxed_slice();
+		assert_eq!(test_api::return_input_boxed_slice(input.clone()), input);
+		assert!(test_api::return_input_boxed_slice(Box::new([])).is_empty());
+	}
+
+	fn test_return_input_btree_collections() {
+		let map = (0..10u32)
+			.map(|i| (i * 7, vec![i as u8; i as usize]))
+			.collect::<BTreeMap<_, _>>();
+		assert_eq!(test_api::return_input_btree_map(map.clone()), map);
+		assert!(test_api::return_input_btree_map(BTreeMap::new()).is_empty());
+
+		let set = [5u64, u64::max_value(), 0].iter().cloned().collect::<BTreeSet<_>>();
+		assert_eq!(test_api::return_input_btree_set(set.clone()), set);
+		assert!(test_api::return_input_btree_set(BTreeSet::new()).is_empty());
+	}
+
+	fn test_return_input_ecdsa_types() {
+		let compressed = ecdsa::Public::Compressed([7; 33]);
+		assert!(test_api::return_input_ecdsa_public(compressed.clone()) == compressed);
+		let full = ecdsa::Public::Full([9; 64]);
+		assert!(test_api::return_input_ecdsa_public(full.clone()) == full);
+
+		let data = (0..65).collect::<Vec<u8>>();
+		let signature = ecdsa::Signature::try_from(&data[..]).unwrap();
+		assert!(test_api::return_input_ecdsa_signature(signature.clone()) == signature);
+	}
+
+	fn test_return_input_encoded_type() {
+		let input = EncodedType {
+			name: b"test".to_vec(),
+			values: vec![1, 2, 65535],
+			flag: Some(true),
+		};
+		assert!(test_api::return_input_encoded_type(input.clone()) == input);
+	}
+
+	fn test_return_input_account_id_20() {
+		let input = AccountId20([7; 20]);
+		assert!(test_api::return_input_account_id_20(input) == input);
+	}
+
+	fn test_return_input_weight() {
+		for input in &[0, 42, u64::max_value()] {
+			assert!(test_api::return_input_weight(Weight(*input)) == Weight(*input));
+		}
+	}
+
+	fn test_return_input_compact_result() {
+		for input in &[Ok(()), Err(3)] {
+			assert_eq!(test_api::return_input_compact_result((*input).into()).0, *input);
+		}
+
+		let signed = [
+			Ok(i16::min_value()), Ok(-1), Ok(i16::max_value()), Err(i8::min_value()), Err(-1),
+		];
+		for input in &signed {
+			assert_eq!(test_api::return_input_signed_compact_result((*input).into()).0, *input);
+		}
+	}
+
+	fn test_return_input_compact_option() {
+		for input in &[None, Some(0), Some(u32::max_value())] {
+			assert_eq!(test_api::return_input_compact_option((*input).into()).0, *input);
+		}
+
+		for input in &[None, Some(false), Some(true)] {
+			assert_eq!(test_api::return_input_bool_compact_option((*input).into()).0, *input);
+		}
+
+		for input in &[None, Some(i32::min_value()), Some(-1), Some(0), Some(i32::max_value())] {
+			assert_eq!(test_api::return_input_signed_compact_option((*input).into()).0, *input);
+		}
+
+		for input in &[None, Some(0), Some(1 << 32), Some(u64::max_value())] {
+			assert_eq!(test_api::return_input_u64_compact_option((*input).into()).0, *input);
+		}
+	}
+
+	fn test_return_input_range() {
+		assert_eq!(test_api::return_input_range(0..10), 0..10);
+		assert_eq!(test_api::return_input_range(5..5), 5..5);
+		assert_eq!(
+			test_api::return_input_range_inclusive(0..=u32::max_value()),
+			0..=u32::max_value(),
+		);
+
+		let mut exhausted = 5..=5;
+		exhausted.next();
+		assert_eq!(test_api::return_input_range_inclusive(exhausted).next(), None);
+	}
+
+	fn test_return_static_or_owned_bytes() {
+		assert_eq!(test_api::return_static_or_owned_bytes(true), &STATIC_BYTES[..]);
+		assert_eq!(test_api::return_static_or_owned_bytes(false), &STATIC_BYTES[..]);
+	}
+
+	fn test_try_from_ffi_value_should_reject_malformed_data() {
+		use sp_runtime_interface::wasm::FromFFIValue;
+
+		// The length prefix announces 4 elements, but only one follows.
+		let malformed = [16u8, 1, 0];
+		let arg = (malformed.len() as u64) << 32 | malformed.as_ptr() as u64;
+		assert!(<Vec<u16>>::try_from_ffi_value(arg).is_err());
+		assert!(<EncodedType<u16>>::try_from_ffi_value(arg).is_err());
+
+		let valid = vec![1u16, 2].encode();
+		let arg = (valid.len() as u64) << 32 | valid.as_ptr() as u64;
+		assert_eq!(<Vec<u16>>::try_from_ffi_value(arg), Ok(vec![1, 2]));
+	}
+
+	fn test_versioned_function() {
+		assert_eq!(test_api::versioned_function_version_1(10), 10);
+		assert_eq!(test_api::versioned_function(10), 11);
+	}
+
+	fn test_renamed_function() {
+		assert_eq!(test_api::renamed_function(10), 12);
+	}
+
+	fn test_pure_function() {
+		assert_eq!(test_api::pure_function(10), 13);
+	}
+
 	fn test_invalid_utf8_data_should_return_an_error() {
 		let data = vec![0, 159, 146, 150];
 		// I'm an evil hacker, trying to hack!
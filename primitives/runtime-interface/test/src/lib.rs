@@ -54,6 +54,11 @@ fn test_return_data() {
 	call_wasm_method::<HostFunctions>("test_return_data");
 }
 
+#[test]
+fn test_return_encoded_data() {
+	call_wasm_method::<HostFunctions>("test_return_encoded_data");
+}
+
 #[test]
 fn test_return_option_data() {
 	call_wasm_method::<HostFunctions>("test_return_option_data");
@@ -72,11 +77,26 @@ fn test_return_value_into_mutable_reference() {
 	call_wasm_method::<HostFunctions>("test_return_value_into_mutable_reference");
 }
 
+#[test]
+fn test_return_value_into_mutable_vec() {
+	call_wasm_method::<HostFunctions>("test_return_value_into_mutable_vec");
+}
+
 #[test]
 fn test_get_and_return_array() {
 	call_wasm_method::<HostFunctions>("test_get_and_return_array");
 }
 
+#[test]
+fn test_get_and_return_large_array() {
+	call_wasm_method::<HostFunctions>("test_get_and_return_large_array");
+}
+
+#[test]
+fn test_get_and_return_array_frees_memory() {
+	call_wasm_method::<HostFunctions>("test_get_and_return_array_frees_memory");
+}
+
 #[test]
 fn test_array_as_mutable_reference() {
 	call_wasm_method::<HostFunctions>("test_array_as_mutable_reference");
@@ -87,6 +107,73 @@ fn test_return_input_public_key() {
 	call_wasm_method::<HostFunctions>("test_return_input_public_key");
 }
 
+#[test]
+fn test_return_input_wide_integers() {
+	call_wasm_method::<HostFunctions>("test_return_input_wide_integers");
+}
+
+#[test]
+fn test_return_input_tuples() {
+	call_wasm_method::<HostFunctions>("test_return_input_tuples");
+}
+
+#[test]
+fn test_return_input_string() {
+	call_wasm_method::<HostFunctions>("test_return_input_string");
+}
+
+#[test]
+fn test_return_input_hashes() {
+	call_wasm_method::<HostFunctions>("test_return_input_hashes");
+}
+
+#[test]
+fn test_return_input_floats() {
+	call_wasm_method::<HostFunctions>("test_return_input_floats");
+}
+
+#[test]
+fn test_versioned_function() {
+	call_wasm_method::<HostFunctions>("test_versioned_function");
+}
+
+#[test]
+fn host_functions_contain_all_versions() {
+	let names = HostFunctions::host_functions().iter().map(|f| f.name()).collect::<Vec<_>>();
+
+	assert!(names.contains(&"ext_test_api_versioned_function_version_1"));
+	assert!(names.contains(&"ext_test_api_versioned_function_version_2"));
+}
+
+#[test]
+fn test_renamed_function() {
+	call_wasm_method::<HostFunctions>("test_renamed_function");
+}
+
+#[test]
+fn host_functions_contain_former_names() {
+	let names = HostFunctions::host_functions().iter().map(|f| f.name()).collect::<Vec<_>>();
+
+	assert!(names.contains(&"ext_test_api_renamed_function_version_1"));
+	assert!(names.contains(&"ext_test_api_old_function_name_version_1"));
+}
+
+#[test]
+fn test_pure_function() {
+	call_wasm_method::<HostFunctions>("test_pure_function");
+}
+
+#[test]
+fn host_functions_carry_purity() {
+	let is_pure = |name| HostFunctions::host_functions()
+		.into_iter()
+		.find(|f| f.name() == name)
+		.map(|f| f.is_pure());
+
+	assert_eq!(is_pure("ext_test_api_pure_function_version_1"), Some(true));
+	assert_eq!(is_pure("ext_test_api_renamed_function_version_1"), Some(false));
+}
+
 #[test]
 #[should_panic(
 	expected = "Other(\"Instantiation: Export ext_test_api_return_input_version_1 not found\")"
@@ -105,6 +192,127 @@ fn test_invalid_utf8_data_should_return_an_error() {
 	call_wasm_method::<HostFunctions>("test_invalid_utf8_data_should_return_an_error");
 }
 
+#[test]
+fn test_return_input_char() {
+	call_wasm_method::<HostFunctions>("test_return_input_char");
+}
+
+#[test]
+#[should_panic(
+	expected =
+		"FunctionExecution(\"ext_test_api_invalid_char_version_1\", \
+		\"Invalid char value provided: 0xd800\")"
+)]
+fn test_invalid_char_should_return_an_error() {
+	call_wasm_method::<HostFunctions>("test_invalid_char_should_return_an_error");
+}
+
+#[test]
+#[should_panic(
+	expected =
+		"FunctionExecution(\"ext_test_api_panic_on_conversion_version_1\", \
+		\"Converting `_data` from its ffi value while executing `panic_on_conversion` from \
+		interface `TestApi` panicked: The conversion always panics\")"
+)]
+fn test_panicking_conversion_should_return_an_error() {
+	call_wasm_method::<HostFunctions>("test_panicking_conversion_should_return_an_error");
+}
+
+#[test]
+fn test_return_input_u16_array() {
+	call_wasm_method::<HostFunctions>("test_return_input_u16_array");
+}
+
+#[test]
+fn test_return_input_non_zero_u32() {
+	call_wasm_method::<HostFunctions>("test_return_input_non_zero_u32");
+}
+
+#[test]
+fn test_return_input_compact() {
+	call_wasm_method::<HostFunctions>("test_return_input_compact");
+}
+
+#[test]
+fn test_return_input_duration() {
+	call_wasm_method::<HostFunctions>("test_return_input_duration");
+}
+
+#[test]
+fn test_return_input_phantom_data() {
+	call_wasm_method::<HostFunctions>("test_return_input_phantom_data");
+}
+
+#[test]
+fn test_return_input_vec_of_vecs() {
+	call_wasm_method::<HostFunctions>("test_return_input_vec_of_vecs");
+}
+
+#[test]
+fn test_return_input_hash_slice() {
+	call_wasm_method::<HostFunctions>("test_return_input_hash_slice");
+}
+
+#[test]
+fn test_return_input_unit() {
+	call_wasm_method::<HostFunctions>("test_return_input_unit");
+}
+
+#[test]
+fn test_return_input_boxed_slice() {
+	call_wasm_method::<HostFunctions>("test_return_input_boxed_slice");
+}
+
+#[test]
+fn test_return_input_btree_collections() {
+	call_wasm_method::<HostFunctions>("test_return_input_btree_collections");
+}
+
+#[test]
+fn test_return_input_ecdsa_types() {
+	call_wasm_method::<HostFunctions>("test_return_input_ecdsa_types");
+}
+
+#[test]
+fn test_return_input_encoded_type() {
+	call_wasm_method::<HostFunctions>("test_return_input_encoded_type");
+}
+
+#[test]
+fn test_return_input_account_id_20() {
+	call_wasm_method::<HostFunctions>("test_return_input_account_id_20");
+}
+
+#[test]
+fn test_return_input_weight() {
+	call_wasm_method::<HostFunctions>("test_return_input_weight");
+}
+
+#[test]
+fn test_return_input_compact_result() {
+	call_wasm_method::<HostFunctions>("test_return_input_compact_result");
+}
+
+#[test]
+fn test_return_input_compact_option() {
+	call_wasm_method::<HostFunctions>("test_return_input_compact_option");
+}
+
+#[test]
+fn test_return_input_range() {
+	call_wasm_method::<HostFunctions>("test_return_input_range");
+}
+
+#[test]
+fn test_return_static_or_owned_bytes() {
+	call_wasm_method::<HostFunctions>("test_return_static_or_owned_bytes");
+}
+
+#[test]
+fn test_try_from_ffi_value_should_reject_malformed_data() {
+	call_wasm_method::<HostFunctions>("test_try_from_ffi_value_should_reject_malformed_data");
+}
+
 #[test]
 fn test_overwrite_native_function_implementation() {
 	call_wasm_method::<HostFunctions>("test_overwrite_native_function_implementation");
@@ -0,0 +1,10 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	fn test() -> &'static [u8] {
+		&[]
+	}
+}
+
+fn main() {}
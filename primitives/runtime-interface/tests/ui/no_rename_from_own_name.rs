@@ -0,0 +1,9 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[renamed_from("test")]
+	fn test() {}
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use sp_runtime_interface::pass_by::PassByInner;
+
+#[derive(PassByInner)]
+struct Test([u8; 20], u8);
+
+fn main() {}
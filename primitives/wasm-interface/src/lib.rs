@@ -200,6 +200,13 @@ pub trait Function: std::panic::RefUnwindSafe + Send + Sync {
 		context: &mut dyn FunctionContext,
 		args: &mut dyn Iterator<Item = Value>,
 	) -> Result<Option<Value>>;
+	/// Returns if this function is pure.
+	///
+	/// The result of a pure function only depends on its arguments and calling it has no side
+	/// effects, so an executor may cache its results.
+	fn is_pure(&self) -> bool {
+		false
+	}
 }
 
 impl PartialEq for dyn Function {
@@ -219,6 +226,28 @@ pub trait FunctionContext {
 	}
 	/// Read memory into the given `dest` buffer from `address`.
 	fn read_memory_into(&self, address: Pointer<u8>, dest: &mut [u8]) -> Result<()>;
+	/// Call `f` with `size` bytes of memory starting at `address`.
+	///
+	/// Implementations should pass a view into the memory to avoid copying the data. The default
+	/// implementation reads the memory into a vector.
+	fn with_memory_slice(
+		&self,
+		address: Pointer<u8>,
+		size: WordSize,
+		f: &mut dyn FnMut(&[u8]),
+	) -> Result<()> {
+		let data = self.read_memory(address, size)?;
+		f(&data);
+		Ok(())
+	}
+	/// Returns the size of the memory in bytes.
+	///
+	/// It is used to reject out of bounds pointers before accessing the memory. The default
+	/// implementation returns `WordSize::max_value()`, for implementations that do not know the
+	/// size. Out of bounds pointers are then only rejected by the memory accesses.
+	fn memory_size(&self) -> WordSize {
+		WordSize::max_value()
+	}
 	/// Write the given data at `address` into the memory.
 	fn write_memory(&mut self, address: Pointer<u8>, data: &[u8]) -> Result<()>;
 	/// Allocate a memory instance of `size` bytes.